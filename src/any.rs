@@ -0,0 +1,323 @@
+use super::*;
+
+use core::any::{Any, TypeId};
+
+/// An arena of objects of any number of types, with a typed id for each
+/// object.
+///
+/// Internally, this keeps one `Arena<T>` per type `T`, which is created when
+/// the first object of that type is allocated. Objects of different types can
+/// be kept in one container, while their ids stay as precise as the ids of a
+/// homogeneous `Arena`.
+///
+/// Objects can be of any `'static` type, including types that are not `Send`
+/// or `Sync`, such as `Rc`. To share the container between threads, use a
+/// [`SyncAnyArena`](./type.SyncAnyArena.html) instead, which only holds `Send +
+/// Sync` objects.
+///
+/// ```
+/// use id_arena::{AnyArena, Id};
+///
+/// struct Function {
+///     name: &'static str,
+///     entry: Id<Block>,
+/// }
+///
+/// struct Block {
+///     instructions: usize,
+/// }
+///
+/// let mut ir = AnyArena::new();
+/// let entry = ir.alloc(Block { instructions: 3 });
+/// let main = ir.alloc(Function { name: "main", entry });
+///
+/// assert_eq!(ir[main].name, "main");
+/// assert_eq!(ir[ir[main].entry].instructions, 3);
+/// ir.get_mut(entry).unwrap().instructions += 1;
+/// assert_eq!(ir.arena::<Block>().unwrap().len(), 1);
+/// assert_eq!(ir.len(), 2);
+/// ```
+pub struct AnyArena<S: ?Sized = dyn Any> {
+    arenas: BTreeMap<TypeId, Box<S>>,
+    len: usize,
+}
+
+/// An `AnyArena` that only holds `Send + Sync` objects, so that it can be
+/// shared between threads.
+///
+/// ```
+/// use id_arena::SyncAnyArena;
+///
+/// let mut arena = SyncAnyArena::default();
+/// let id = arena.alloc(42u32);
+///
+/// let arena = std::thread::spawn(move || arena).join().unwrap();
+/// assert_eq!(arena[id], 42);
+/// ```
+pub type SyncAnyArena = AnyArena<dyn Any + Send + Sync>;
+
+/// The type-erased storage of an `AnyArena` or a `World`, which determines the
+/// objects or components that it can hold.
+///
+/// `dyn Any`, the default, holds objects of any `'static` type.
+/// `dyn Any + Send + Sync` only holds `Send + Sync` objects, so that the
+/// container is `Send` and `Sync` as well.
+pub trait AnyStorage: 'static {
+    #[doc(hidden)]
+    fn as_any(&self) -> &dyn Any;
+
+    #[doc(hidden)]
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl AnyStorage for dyn Any {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl AnyStorage for dyn Any + Send + Sync {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Objects that can be held in the `AnyStorage` `S`.
+///
+/// Every `'static` type can be held in `dyn Any`, and every `Send + Sync +
+/// 'static` type in `dyn Any + Send + Sync`.
+pub trait StorableIn<S: ?Sized>: Sized + 'static {
+    #[doc(hidden)]
+    fn store_arena(arena: Arena<Self>) -> Box<S>;
+
+    #[doc(hidden)]
+    fn store_components(components: ArenaMap<Entity, Self>) -> Box<S>;
+}
+
+impl<T> StorableIn<dyn Any> for T
+where
+    T: 'static,
+{
+    #[inline]
+    fn store_arena(arena: Arena<T>) -> Box<dyn Any> {
+        Box::new(arena)
+    }
+
+    #[inline]
+    fn store_components(components: ArenaMap<Entity, T>) -> Box<dyn Any> {
+        Box::new(components)
+    }
+}
+
+impl<T> StorableIn<dyn Any + Send + Sync> for T
+where
+    T: Send + Sync + 'static,
+{
+    #[inline]
+    fn store_arena(arena: Arena<T>) -> Box<dyn Any + Send + Sync> {
+        Box::new(arena)
+    }
+
+    #[inline]
+    fn store_components(components: ArenaMap<Entity, T>) -> Box<dyn Any + Send + Sync> {
+        Box::new(components)
+    }
+}
+
+impl<S> Default for AnyArena<S>
+where
+    S: ?Sized,
+{
+    #[inline]
+    fn default() -> Self {
+        AnyArena {
+            arenas: BTreeMap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<S> fmt::Debug for AnyArena<S>
+where
+    S: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnyArena")
+            .field("types", &self.arenas.len())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl AnyArena {
+    /// Construct a new, empty `AnyArena`.
+    #[inline]
+    pub fn new() -> AnyArena {
+        Default::default()
+    }
+}
+
+impl<S> AnyArena<S>
+where
+    S: ?Sized + AnyStorage,
+{
+    /// Allocate `item` within the arena of `T`s, creating that arena if this
+    /// is the first `T`, and return its id.
+    pub fn alloc<T>(&mut self, item: T) -> Id<T>
+    where
+        T: StorableIn<S>,
+    {
+        self.len += 1;
+        self.arenas
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| T::store_arena(Arena::new()))
+            .as_any_mut()
+            .downcast_mut::<Arena<T>>()
+            .unwrap()
+            .alloc(item)
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    #[inline]
+    pub fn get<T>(&self, id: Id<T>) -> Option<&T>
+    where
+        T: StorableIn<S>,
+    {
+        self.arena::<T>()?.get(id)
+    }
+
+    /// Get an exclusive reference to the object associated with the given `id`
+    /// if it exists.
+    #[inline]
+    pub fn get_mut<T>(&mut self, id: Id<T>) -> Option<&mut T>
+    where
+        T: StorableIn<S>,
+    {
+        self.arenas
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<Arena<T>>()?
+            .get_mut(id)
+    }
+
+    /// Get the arena of this container's `T`s, if any `T` has been
+    /// allocated.
+    #[inline]
+    pub fn arena<T>(&self) -> Option<&Arena<T>>
+    where
+        T: StorableIn<S>,
+    {
+        self.arenas.get(&TypeId::of::<T>())?.as_any().downcast_ref()
+    }
+
+    /// Get the number of objects of every type allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<S, T> ops::Index<Id<T>> for AnyArena<S>
+where
+    S: ?Sized + AnyStorage,
+    T: StorableIn<S>,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: Id<T>) -> &T {
+        match self.arena::<T>() {
+            Some(arena) => &arena[id],
+            None => no_arena_panic::<T>(),
+        }
+    }
+}
+
+impl<S, T> ops::IndexMut<Id<T>> for AnyArena<S>
+where
+    S: ?Sized + AnyStorage,
+    T: StorableIn<S>,
+{
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, id: Id<T>) -> &mut T {
+        match self
+            .arenas
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|arena| arena.as_any_mut().downcast_mut::<Arena<T>>())
+        {
+            Some(arena) => &mut arena[id],
+            None => no_arena_panic::<T>(),
+        }
+    }
+}
+
+#[cold]
+#[track_caller]
+fn no_arena_panic<T>() -> ! {
+    panic!(
+        "cannot index `AnyArena`: no objects of type `{}` have been allocated",
+        core::any::type_name::<T>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_any_arenas_are_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SyncAnyArena>();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn any_arenas_hold_objects_that_are_not_send() {
+        let mut arena = AnyArena::new();
+        let id = arena.alloc(std::rc::Rc::new(core::cell::Cell::new(1u32)));
+        arena[id].set(2);
+        assert_eq!(arena[id].get(), 2);
+    }
+
+    #[test]
+    fn any_arena_keeps_types_apart() {
+        let mut arena = AnyArena::new();
+        let a = arena.alloc(1u32);
+        let b = arena.alloc(2u64);
+        arena[a] += 10;
+        assert_eq!((arena[a], arena[b]), (11, 2));
+
+        // Ids from another `AnyArena` belong to a different arena of `u32`s.
+        let mut other = AnyArena::new();
+        let c = other.alloc(0u32);
+        assert_eq!(arena.get(c), None);
+        assert_eq!(arena.get(Arena::<bool>::new().next_id()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "no objects of type `bool` have been allocated")]
+    fn any_arena_index_panic_names_missing_type() {
+        let _ = AnyArena::new()[Arena::<bool>::new().next_id()];
+    }
+}
@@ -0,0 +1,299 @@
+use super::*;
+
+use rkyv::api::high::{HighDeserializer, HighSerializer, HighValidator};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::rancor;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::vec::ArchivedVec;
+use rkyv::{Archive, Archived, Deserialize, Serialize};
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Serialize this arena's items with `rkyv`, into bytes that an
+    /// `ArchivedArena` can access in place without deserializing them.
+    ///
+    /// Each item keeps its index, so this arena's ids can be used with the
+    /// `ArchivedArena` if it is given the same arena id.
+    ///
+    /// ```
+    /// use id_arena::{Arena, ArchivedArena};
+    ///
+    /// let mut arena = Arena::<String>::with_arena_id(1);
+    /// let a = arena.alloc("Albert".to_string());
+    /// let b = arena.alloc("Bob".to_string());
+    ///
+    /// let bytes = arena.to_archive_bytes().unwrap();
+    /// let archived = ArchivedArena::<String>::access_with_arena_id(&bytes, 1).unwrap();
+    ///
+    /// assert_eq!(archived.len(), 2);
+    /// assert_eq!(archived[a], "Albert");
+    /// assert_eq!(archived.get(b).map(|s| s.as_str()), Some("Bob"));
+    ///
+    /// // Invalid bytes are rejected before they are accessed.
+    /// let truncated = &bytes[..bytes.len() - 4];
+    /// assert!(ArchivedArena::<String>::access(truncated).is_err());
+    /// ```
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rkyv` feature of this crate to be enabled.
+    pub fn to_archive_bytes(&self) -> Result<AlignedVec, rancor::Error>
+    where
+        T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    {
+        rkyv::to_bytes(&self.items)
+    }
+}
+
+impl<T> fmt::Debug for ArchivedId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_id(), f)
+    }
+}
+
+impl<T> ArchivedId<T> {
+    /// Get the `Id` that this archived id was serialized from, to look up its
+    /// object in an `ArchivedArena` or in a deserialized `Arena`.
+    ///
+    /// ```
+    /// use id_arena::{Arena, ArchivedArena, Id};
+    ///
+    /// let mut names = Arena::<String>::with_arena_id(1);
+    /// let alice = names.alloc("Alice".to_string());
+    ///
+    /// let mut friends = Arena::<Id<String>>::with_arena_id(2);
+    /// let friend = friends.alloc(alice);
+    ///
+    /// let bytes = friends.to_archive_bytes().unwrap();
+    /// let archived = ArchivedArena::<Id<String>>::access_with_arena_id(&bytes, 2).unwrap();
+    ///
+    /// assert_eq!(archived[friend].to_id(), alice);
+    /// assert_eq!(names[archived[friend].to_id()], "Alice");
+    /// ```
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rkyv` feature of this crate to be enabled.
+    #[inline]
+    pub fn to_id(&self) -> Id<T> {
+        Id {
+            idx: self.idx.to_native() as usize,
+            arena_id: self.arena_id.to_native(),
+            _ty: PhantomData,
+        }
+    }
+}
+
+/// A read-only view of an arena that was serialized with
+/// [`Arena::to_archive_bytes`](./struct.Arena.html#method.to_archive_bytes),
+/// which accesses its archived items in place.
+///
+/// # Features
+///
+/// This API requires the `rkyv` feature of this crate to be enabled.
+pub struct ArchivedArena<'a, T, A = DefaultArenaBehavior<T>>
+where
+    T: Archive,
+{
+    arena_id: u32,
+    items: &'a [Archived<T>],
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T, A> fmt::Debug for ArchivedArena<'a, T, A>
+where
+    T: Archive,
+    Archived<T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArchivedArena")
+            .field("arena_id", &self.arena_id)
+            .field("items", &self.items)
+            .finish()
+    }
+}
+
+impl<'a, T, A> Clone for ArchivedArena<'a, T, A>
+where
+    T: Archive,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, A> Copy for ArchivedArena<'a, T, A> where T: Archive {}
+
+impl<'a, T, A> ArchivedArena<'a, T, A>
+where
+    T: Archive,
+    A: ArenaBehavior,
+{
+    /// Validate `bytes` and access the archived arena within them.
+    ///
+    /// The archived arena gets a new arena id, as with `Arena::new`.
+    ///
+    /// `bytes` must be aligned like the bytes returned from
+    /// `Arena::to_archive_bytes`.
+    pub fn access(bytes: &'a [u8]) -> Result<ArchivedArena<'a, T, A>, rancor::Error>
+    where
+        Archived<T>: for<'b> CheckBytes<HighValidator<'b, rancor::Error>>,
+    {
+        ArchivedArena::access_inner(bytes, A::new_arena_id())
+    }
+
+    /// Validate `bytes` and access the archived arena within them, giving it
+    /// the given arena id.
+    ///
+    /// Use the arena id that the serialized arena was created with by
+    /// `Arena::with_arena_id` to use its ids with the archived arena.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `arena_id` is not less than `2^31`.
+    #[track_caller]
+    pub fn access_with_arena_id(
+        bytes: &'a [u8],
+        arena_id: u32,
+    ) -> Result<ArchivedArena<'a, T, A>, rancor::Error>
+    where
+        Archived<T>: for<'b> CheckBytes<HighValidator<'b, rancor::Error>>,
+    {
+        ArchivedArena::access_inner(bytes, explicit_arena_id(arena_id))
+    }
+
+    fn access_inner(
+        bytes: &'a [u8],
+        arena_id: u32,
+    ) -> Result<ArchivedArena<'a, T, A>, rancor::Error>
+    where
+        Archived<T>: for<'b> CheckBytes<HighValidator<'b, rancor::Error>>,
+    {
+        let items = rkyv::access::<ArchivedVec<Archived<T>>, rancor::Error>(bytes)?;
+        Ok(ArchivedArena {
+            arena_id,
+            items: items.as_slice(),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Get a shared reference to the archived object associated with the given
+    /// `id` if it exists.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&'a Archived<T>> {
+        self.try_get(id).ok()
+    }
+
+    /// Get a shared reference to the archived object associated with the given
+    /// `id`, or an error describing why there is none.
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&'a Archived<T>, ArenaError> {
+        let index = check_id(
+            self.arena_id,
+            self.items.len(),
+            A::arena_id(id),
+            A::index(id),
+        )?;
+        Ok(&self.items[index])
+    }
+
+    /// Iterate over this arena's archived items and their ids.
+    #[inline]
+    pub fn iter(&self) -> Iter<'a, Archived<T>, A> {
+        Iter {
+            arena_id: self.arena_id,
+            iter: self.items.iter().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Deserialize every archived object into a new `Arena` with this
+    /// archived arena's arena id.
+    pub fn to_arena(&self) -> Result<Arena<T, A>, rancor::Error>
+    where
+        Archived<T>: Deserialize<T, HighDeserializer<rancor::Error>>,
+    {
+        let items = self
+            .items
+            .iter()
+            .map(rkyv::deserialize)
+            .collect::<Result<_, _>>()?;
+        Ok(Arena {
+            arena_id: self.arena_id,
+            items,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Get the number of objects in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if this arena contains no objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<'a, T, A> ops::Index<A::Id> for ArchivedArena<'a, T, A>
+where
+    T: Archive,
+    A: ArenaBehavior,
+{
+    type Output = Archived<T>;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &Archived<T> {
+        match self.try_get(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    struct Edge {
+        from: Id<u32>,
+        to: Id<u32>,
+    }
+
+    #[test]
+    fn archived_ids_round_trip() {
+        let mut nodes = Arena::<u32>::with_arena_id(3);
+        let a = nodes.alloc(1);
+        let b = nodes.alloc(2);
+
+        let mut edges = Arena::<Edge>::with_arena_id(4);
+        let edge = edges.alloc(Edge { from: a, to: b });
+
+        let bytes = edges.to_archive_bytes().unwrap();
+        let archived = ArchivedArena::<Edge>::access_with_arena_id(&bytes, 4).unwrap();
+        assert_eq!(archived[edge].from.to_id(), a);
+        assert_eq!(archived[edge].to.to_id(), b);
+        assert_eq!(
+            std::format!("{:?}", archived[edge].to),
+            std::format!("{:?}", b)
+        );
+
+        let nodes =
+            ArchivedArena::<u32>::access_with_arena_id(&nodes.to_archive_bytes().unwrap(), 3)
+                .map(|nodes| nodes[archived[edge].to.to_id()].to_native())
+                .unwrap();
+        assert_eq!(nodes, 2);
+
+        let deserialized = archived.to_arena().unwrap();
+        assert_eq!(deserialized[edge], Edge { from: a, to: b });
+    }
+}
@@ -0,0 +1,428 @@
+use super::*;
+
+/// The error returned when allocating into an `ArrayArena` that is already
+/// full.
+///
+/// The item that could not be allocated is handed back to the caller.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T>(T);
+
+impl<T> CapacityError<T> {
+    /// Get back the item that could not be allocated.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CapacityError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the arena is full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for CapacityError<T> {}
+
+/// A fixed-capacity arena of at most `N` objects of type `T`.
+///
+/// Unlike `Arena`, an `ArrayArena` stores its items inline and never allocates,
+/// so it is available even when this crate's `"alloc"` feature is disabled.
+/// When the arena is full, allocation fails with a `CapacityError` rather than
+/// growing.
+///
+/// ```
+/// use id_arena::ArrayArena;
+///
+/// let mut arena = ArrayArena::<&str, 2>::new();
+///
+/// let a = arena.alloc("Albert").unwrap();
+/// assert_eq!(arena[a], "Albert");
+///
+/// arena[a] = "Alice";
+/// assert_eq!(arena[a], "Alice");
+///
+/// arena.alloc("Bob").unwrap();
+/// let err = arena.alloc("Carol").unwrap_err();
+/// assert_eq!(err.into_inner(), "Carol");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArrayArena<T, const N: usize, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    // Invariant: `items[..len]` are all `Some` and `items[len..]` are all
+    // `None`.
+    items: [Option<T>; N],
+    len: usize,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<T, const N: usize, A> Default for ArrayArena<T, N, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn default() -> ArrayArena<T, N, A> {
+        ArrayArena {
+            arena_id: A::new_arena_id(),
+            items: core::array::from_fn(|_| None),
+            len: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize, A> ArrayArena<T, N, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `ArrayArena`.
+    ///
+    /// ```
+    /// use id_arena::ArrayArena;
+    ///
+    /// let mut arena = ArrayArena::<usize, 16>::new();
+    /// arena.alloc(42).unwrap();
+    /// ```
+    #[inline]
+    pub fn new() -> ArrayArena<T, N, A> {
+        Default::default()
+    }
+
+    /// Allocate `item` within this arena and return its id.
+    ///
+    /// If the arena is already full, then `item` is returned inside the
+    /// `CapacityError`.
+    ///
+    /// ```
+    /// use id_arena::ArrayArena;
+    ///
+    /// let mut arena = ArrayArena::<usize, 1>::new();
+    /// assert!(arena.alloc(42).is_ok());
+    /// assert!(arena.alloc(43).is_err());
+    /// ```
+    #[inline]
+    pub fn alloc(&mut self, item: T) -> Result<A::Id, CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError(item));
+        }
+        let id = self.next_id();
+        self.items[self.len] = Some(item);
+        self.len += 1;
+        Ok(id)
+    }
+
+    /// Allocate an item with the id that it will be assigned.
+    ///
+    /// See `Arena::alloc_with_id` for details. If the arena is already full,
+    /// then `f` is not called.
+    ///
+    /// ```
+    /// use id_arena::{ArrayArena, Id};
+    ///
+    /// struct Cat {
+    ///     id: Id<Cat>,
+    /// }
+    ///
+    /// let mut arena = ArrayArena::<Cat, 4>::new();
+    ///
+    /// let kitty = arena.alloc_with_id(|id| Cat { id }).unwrap();
+    /// assert_eq!(arena[kitty].id, kitty);
+    /// ```
+    #[inline]
+    pub fn alloc_with_id(
+        &mut self,
+        f: impl FnOnce(A::Id) -> T,
+    ) -> Result<A::Id, CapacityError<()>> {
+        if self.is_full() {
+            return Err(CapacityError(()));
+        }
+        let id = self.next_id();
+        let val = f(id);
+        self.alloc(val).map_err(|_| CapacityError(()))
+    }
+
+    /// Get the id that will be used for the next item allocated into this
+    /// arena.
+    ///
+    /// If the arena is full, the returned id will not be usable.
+    #[inline]
+    pub fn next_id(&self) -> A::Id {
+        A::new_id(self.arena_id, self.len)
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    ///
+    /// ```
+    /// use id_arena::ArrayArena;
+    ///
+    /// let mut arena = ArrayArena::<usize, 4>::new();
+    /// let id = arena.alloc(42).unwrap();
+    /// assert!(arena.get(id).is_some());
+    ///
+    /// let other_arena = ArrayArena::<usize, 4>::new();
+    /// assert!(other_arena.get(id).is_none());
+    /// ```
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        if A::arena_id(id) != self.arena_id {
+            None
+        } else {
+            self.items[..self.len].get(A::index(id))?.as_ref()
+        }
+    }
+
+    /// Get an exclusive reference to the object associated with the given `id`
+    /// if it exists.
+    ///
+    /// ```
+    /// use id_arena::ArrayArena;
+    ///
+    /// let mut arena = ArrayArena::<usize, 4>::new();
+    /// let id = arena.alloc(42).unwrap();
+    /// *arena.get_mut(id).unwrap() += 1;
+    /// assert_eq!(arena[id], 43);
+    /// ```
+    #[inline]
+    pub fn get_mut(&mut self, id: A::Id) -> Option<&mut T> {
+        if A::arena_id(id) != self.arena_id {
+            None
+        } else {
+            self.items[..self.len].get_mut(A::index(id))?.as_mut()
+        }
+    }
+
+    /// Get a shared reference to the object associated with the given `id`, or
+    /// an error describing why there is none.
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&T, ArenaError> {
+        let index = check_id(self.arena_id, self.len, A::arena_id(id), A::index(id))?;
+        Ok(self.items[index]
+            .as_ref()
+            .expect("items below `len` are always allocated"))
+    }
+
+    /// Get an exclusive reference to the object associated with the given
+    /// `id`, or an error describing why there is none.
+    #[inline]
+    pub fn try_get_mut(&mut self, id: A::Id) -> Result<&mut T, ArenaError> {
+        let index = check_id(self.arena_id, self.len, A::arena_id(id), A::index(id))?;
+        Ok(self.items[index]
+            .as_mut()
+            .expect("items below `len` are always allocated"))
+    }
+
+    /// Iterate over this arena's items and their ids.
+    ///
+    /// ```
+    /// use id_arena::ArrayArena;
+    ///
+    /// let mut arena = ArrayArena::<&str, 4>::new();
+    ///
+    /// arena.alloc("hello").unwrap();
+    /// arena.alloc("hi").unwrap();
+    ///
+    /// for (id, s) in arena.iter() {
+    ///     assert_eq!(arena.get(id).unwrap(), s);
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> ArrayIter<'_, T, A> {
+        IntoIterator::into_iter(self)
+    }
+
+    /// Iterate over this arena's items and their ids, allowing mutation of each
+    /// item.
+    #[inline]
+    pub fn iter_mut(&mut self) -> ArrayIterMut<'_, T, A> {
+        IntoIterator::into_iter(self)
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the maximum number of objects this arena can hold, which is `N`.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if this arena cannot hold any more objects.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+}
+
+impl<T, const N: usize, A> ops::Index<A::Id> for ArrayArena<T, N, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        match self.try_get(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+impl<T, const N: usize, A> ops::IndexMut<A::Id> for ArrayArena<T, N, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, id: A::Id) -> &mut T {
+        match self.try_get_mut(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+/// An iterator over `(Id, &T)` pairs in an `ArrayArena`.
+///
+/// See [the `ArrayArena::iter()` method](./struct.ArrayArena.html#method.iter)
+/// for details.
+#[derive(Debug)]
+pub struct ArrayIter<'a, T: 'a, A: 'a> {
+    arena_id: u32,
+    iter: iter::Enumerate<slice::Iter<'a, Option<T>>>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for ArrayIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, item) = self.iter.next()?;
+        Some((A::new_id(self.arena_id, idx), item.as_ref()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for ArrayIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (idx, item) = self.iter.next_back()?;
+        Some((A::new_id(self.arena_id, idx), item.as_ref()?))
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for ArrayIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<'a, T, const N: usize, A> IntoIterator for &'a ArrayArena<T, N, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+    type IntoIter = ArrayIter<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> ArrayIter<'a, T, A> {
+        ArrayIter {
+            arena_id: self.arena_id,
+            iter: self.items[..self.len].iter().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// An iterator over `(Id, &mut T)` pairs in an `ArrayArena`.
+///
+/// See [the `ArrayArena::iter_mut()`
+/// method](./struct.ArrayArena.html#method.iter_mut) for details.
+#[derive(Debug)]
+pub struct ArrayIterMut<'a, T: 'a, A: 'a> {
+    arena_id: u32,
+    iter: iter::Enumerate<slice::IterMut<'a, Option<T>>>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for ArrayIterMut<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a mut T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, item) = self.iter.next()?;
+        Some((A::new_id(self.arena_id, idx), item.as_mut()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for ArrayIterMut<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (idx, item) = self.iter.next_back()?;
+        Some((A::new_id(self.arena_id, idx), item.as_mut()?))
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for ArrayIterMut<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<'a, T, const N: usize, A> IntoIterator for &'a mut ArrayArena<T, N, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a mut T);
+    type IntoIter = ArrayIterMut<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> ArrayIterMut<'a, T, A> {
+        ArrayIterMut {
+            arena_id: self.arena_id,
+            iter: self.items[..self.len].iter_mut().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use super::*;
+
+/// An invariant lifetime that ties `BrandedId`s to the one `BrandedArena` that
+/// created them.
+type Brand<'brand> = PhantomData<fn(&'brand ()) -> &'brand ()>;
+
+/// An identifier for an object allocated within a `BrandedArena`.
+///
+/// A `BrandedId` carries the unique lifetime brand of the arena that created
+/// it, so it can only be used with that arena and can never escape the
+/// `Arena::scope` closure. Use `BrandedArena::unbrand` to turn it back into a
+/// regular id.
+pub struct BrandedId<'brand> {
+    idx: usize,
+    _brand: Brand<'brand>,
+}
+
+impl<'brand> fmt::Debug for BrandedId<'brand> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BrandedId").field("idx", &self.idx).finish()
+    }
+}
+
+impl<'brand> Copy for BrandedId<'brand> {}
+
+impl<'brand> Clone for BrandedId<'brand> {
+    #[inline]
+    fn clone(&self) -> BrandedId<'brand> {
+        *self
+    }
+}
+
+impl<'brand> PartialEq for BrandedId<'brand> {
+    #[inline]
+    fn eq(&self, rhs: &Self) -> bool {
+        self.idx == rhs.idx
+    }
+}
+
+impl<'brand> Eq for BrandedId<'brand> {}
+
+impl<'brand> Hash for BrandedId<'brand> {
+    #[inline]
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        self.idx.hash(h);
+    }
+}
+
+impl<'brand> PartialOrd for BrandedId<'brand> {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl<'brand> Ord for BrandedId<'brand> {
+    fn cmp(&self, rhs: &Self) -> Ordering {
+        self.idx.cmp(&rhs.idx)
+    }
+}
+
+impl<'brand> BrandedId<'brand> {
+    /// Get the index within the arena that this id refers to.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+}
+
+/// A view of an `Arena` whose ids are branded with a unique lifetime.
+///
+/// Mixing up ids from different branded arenas is a compile error rather than
+/// a runtime failure, so lookups skip the `arena_id` check that `Arena`'s
+/// methods perform, and they never fail.
+///
+/// See [the `Arena::scope()` method](./struct.Arena.html#method.scope) for
+/// details.
+#[derive(Debug)]
+pub struct BrandedArena<'brand, 'a, T, A = DefaultArenaBehavior<T>> {
+    arena: &'a mut Arena<T, A>,
+    _brand: Brand<'brand>,
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Call `f` with a branded view of this arena.
+    ///
+    /// Ids allocated through the `BrandedArena` are branded with a lifetime
+    /// that is unique to this call, so they cannot be used with any other
+    /// arena, and they cannot escape `f`.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<&str>::new();
+    ///
+    /// let id = arena.scope(|mut arena| {
+    ///     let a = arena.alloc("Albert");
+    ///     arena[a] = "Alice";
+    ///     assert_eq!(arena[a], "Alice");
+    ///     arena.unbrand(a)
+    /// });
+    ///
+    /// assert_eq!(arena[id], "Alice");
+    /// ```
+    ///
+    /// Using an id with a different arena does not compile:
+    ///
+    /// ```compile_fail
+    /// use id_arena::Arena;
+    ///
+    /// let mut a = Arena::<u32>::new();
+    /// let mut b = Arena::<u32>::new();
+    ///
+    /// a.scope(|mut a| {
+    ///     b.scope(|b| {
+    ///         let id = a.alloc(1);
+    ///         assert_eq!(b[id], 1);
+    ///     })
+    /// });
+    /// ```
+    ///
+    /// Neither does letting a branded id escape:
+    ///
+    /// ```compile_fail
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<u32>::new();
+    /// let id = arena.scope(|mut arena| arena.alloc(1));
+    /// ```
+    #[inline]
+    pub fn scope<'a, F, R>(&'a mut self, f: F) -> R
+    where
+        F: for<'brand> FnOnce(BrandedArena<'brand, 'a, T, A>) -> R,
+    {
+        f(BrandedArena {
+            arena: self,
+            _brand: PhantomData,
+        })
+    }
+}
+
+impl<'brand, 'a, T, A> BrandedArena<'brand, 'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn brand_index(&self, idx: usize) -> BrandedId<'brand> {
+        BrandedId {
+            idx,
+            _brand: PhantomData,
+        }
+    }
+
+    /// Allocate `item` within this arena and return its branded id.
+    #[inline]
+    pub fn alloc(&mut self, item: T) -> BrandedId<'brand> {
+        let id = self.brand_index(self.arena.len());
+        self.arena.alloc(item);
+        id
+    }
+
+    /// Get a shared reference to the object associated with the given `id`.
+    #[inline]
+    pub fn get(&self, id: BrandedId<'brand>) -> &T {
+        &self.arena.items[id.idx]
+    }
+
+    /// Get an exclusive reference to the object associated with the given
+    /// `id`.
+    #[inline]
+    pub fn get_mut(&mut self, id: BrandedId<'brand>) -> &mut T {
+        &mut self.arena.items[id.idx]
+    }
+
+    /// Brand a regular id, if it refers to an object in this arena.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<u32>::new();
+    /// let id = arena.alloc(42);
+    ///
+    /// arena.scope(|arena| {
+    ///     let branded = arena.brand(id).unwrap();
+    ///     assert_eq!(arena[branded], 42);
+    /// });
+    ///
+    /// let other_arena = Arena::<u32>::new();
+    /// arena.scope(|arena| assert!(arena.brand(other_arena.next_id()).is_none()));
+    /// ```
+    #[inline]
+    pub fn brand(&self, id: A::Id) -> Option<BrandedId<'brand>> {
+        self.arena.get(id)?;
+        Some(self.brand_index(A::index(id)))
+    }
+
+    /// Turn a branded id back into a regular id, which may escape the scope.
+    #[inline]
+    pub fn unbrand(&self, id: BrandedId<'brand>) -> A::Id {
+        A::new_id(self.arena.arena_id, id.idx)
+    }
+
+    /// Iterate over this arena's items and their branded ids.
+    #[inline]
+    pub fn iter(&self) -> BrandedIter<'brand, '_, T> {
+        BrandedIter {
+            iter: self.arena.items.iter().enumerate(),
+            _brand: PhantomData,
+        }
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+}
+
+impl<'brand, 'a, T, A> ops::Index<BrandedId<'brand>> for BrandedArena<'brand, 'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    fn index(&self, id: BrandedId<'brand>) -> &T {
+        self.get(id)
+    }
+}
+
+impl<'brand, 'a, T, A> ops::IndexMut<BrandedId<'brand>> for BrandedArena<'brand, 'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn index_mut(&mut self, id: BrandedId<'brand>) -> &mut T {
+        self.get_mut(id)
+    }
+}
+
+/// An iterator over `(BrandedId, &T)` pairs in a branded arena.
+///
+/// See [the `BrandedArena::iter()` method](./struct.BrandedArena.html#method.iter)
+/// for details.
+#[derive(Debug)]
+pub struct BrandedIter<'brand, 'a, T: 'a> {
+    iter: iter::Enumerate<slice::Iter<'a, T>>,
+    _brand: Brand<'brand>,
+}
+
+impl<'brand, 'a, T: 'a> Iterator for BrandedIter<'brand, 'a, T> {
+    type Item = (BrandedId<'brand>, &'a T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, item) = self.iter.next()?;
+        let id = BrandedId {
+            idx,
+            _brand: PhantomData,
+        };
+        Some((id, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'brand, 'a, T: 'a> ExactSizeIterator for BrandedIter<'brand, 'a, T> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
@@ -0,0 +1,607 @@
+use super::*;
+
+/// A type that can be encoded into, and decoded from, a compact binary format.
+///
+/// Integers are encoded as variable-length LEB128 varints, so small values take
+/// a single byte. Signed integers are zigzag-encoded first.
+///
+/// ```
+/// use id_arena::{Codec, DecodeError, Decoder, Encoder};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// impl Codec for Point {
+///     fn encode(&self, encoder: &mut Encoder) {
+///         self.x.encode(encoder);
+///         self.y.encode(encoder);
+///     }
+///
+///     fn decode(decoder: &mut Decoder) -> Result<Point, DecodeError> {
+///         Ok(Point {
+///             x: i32::decode(decoder)?,
+///             y: i32::decode(decoder)?,
+///         })
+///     }
+/// }
+///
+/// let mut encoder = Encoder::new();
+/// Point { x: 1, y: -2 }.encode(&mut encoder);
+/// assert_eq!(encoder.as_bytes(), [2, 3]);
+///
+/// let mut decoder = Decoder::new(encoder.as_bytes());
+/// assert_eq!(Point::decode(&mut decoder), Ok(Point { x: 1, y: -2 }));
+/// assert_eq!(decoder.finish(), Ok(()));
+/// ```
+pub trait Codec: Sized {
+    /// Append the encoding of `self` to `encoder`.
+    fn encode(&self, encoder: &mut Encoder);
+
+    /// Decode a value from the front of `decoder`'s remaining bytes.
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError>;
+}
+
+/// Tag an encoded id's `value` with whether it is a foreign id, which is
+/// followed by its arena id, in its lowest bit.
+#[inline]
+fn tag_id(value: u64, foreign: bool) -> u64 {
+    value << 1 | u64::from(foreign)
+}
+
+/// A buffer that values are encoded into.
+///
+/// See [the `Codec` trait](./trait.Codec.html) for details.
+#[derive(Clone, Debug, Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+    relative_ids: bool,
+    /// The arena item that is being encoded, if any.
+    pub(crate) item: Option<ItemContext>,
+}
+
+impl Encoder {
+    /// Construct a new, empty `Encoder`.
+    #[inline]
+    pub fn new() -> Encoder {
+        Default::default()
+    }
+
+    /// Append a single byte.
+    #[inline]
+    pub fn write_u8(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    /// Append raw bytes, without a length prefix.
+    #[inline]
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Append `value` as a LEB128 varint.
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    /// Append `id` as a varint of its index.
+    ///
+    /// Within an item of an arena that is being encoded, ids of that arena are
+    /// encoded as their index alone. If relative ids are enabled, then the
+    /// difference between `id`'s index and the item's index is encoded
+    /// instead, which is smaller for ids that refer to nearby items. The arena
+    /// id is not encoded, since it is only meaningful within this process.
+    /// [`Decoder::read_id`](./struct.Decoder.html#method.read_id) gives the
+    /// decoded id the arena id of the decoded arena instead.
+    ///
+    /// Ids of any other arena, and ids outside of an arena item, are encoded
+    /// as their arena id and index, and decoded as is. They are only
+    /// meaningful in another process if their arena's arena id is
+    /// reproducible, e.g. an arena created with `Arena::with_arena_id`;
+    /// otherwise, convert them to [`StableId`](./struct.StableId.html)s.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `id` belongs to the arena that is being encoded, but is out
+    /// of bounds for it, since the encoded id could not be decoded.
+    #[track_caller]
+    pub fn write_id<A>(&mut self, id: A::Id)
+    where
+        A: ArenaBehavior,
+    {
+        let index = A::index(id);
+        let item = match self.item {
+            Some(item) if item.arena_id == A::arena_id(id) => item,
+            _ => {
+                self.write_varint(tag_id(index as u64, true));
+                self.write_varint(u64::from(A::arena_id(id)));
+                return;
+            }
+        };
+        if let Err(err) = check_id(item.arena_id, item.len, A::arena_id(id), index) {
+            panic!("cannot encode id: {}", err);
+        }
+        let value = if item.relative_ids {
+            let offset = index as i64 - item.index as i64;
+            ((offset << 1) ^ (offset >> 63)) as u64
+        } else {
+            index as u64
+        };
+        self.write_varint(tag_id(value, false));
+    }
+
+    /// Set whether ids within arena items are encoded relative to the index of
+    /// the item that contains them. Defaults to `false`.
+    ///
+    /// This only affects arenas that are encoded afterwards, which record
+    /// whether their ids are relative, so decoding them needs no setting.
+    #[inline]
+    pub fn set_relative_ids(&mut self, relative: bool) {
+        self.relative_ids = relative;
+    }
+
+    /// Get the bytes encoded so far.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Get the bytes encoded so far, consuming this encoder.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Remove all encoded bytes, keeping the allocated buffer for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+}
+
+/// A cursor over bytes that values are decoded from.
+///
+/// See [the `Codec` trait](./trait.Codec.html) for details.
+#[derive(Clone, Debug)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    /// The arena item that is being decoded, if any.
+    pub(crate) item: Option<ItemContext>,
+}
+
+/// Where ids that are decoded within an arena item point to.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ItemContext {
+    pub(crate) arena_id: u32,
+    pub(crate) len: usize,
+    pub(crate) index: usize,
+    pub(crate) relative_ids: bool,
+}
+
+impl<'a> Decoder<'a> {
+    /// Construct a new `Decoder` that decodes values from `bytes`.
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Decoder<'a> {
+        Decoder { bytes, item: None }
+    }
+
+    /// Read a single byte.
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let (&byte, rest) = self.bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    /// Read `len` raw bytes.
+    #[inline]
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if len > self.bytes.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    /// Read a LEB128 varint.
+    pub fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift == 63 && byte > 1 {
+                return Err(DecodeError::Invalid("varint overflows 64 bits"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read an id that was written with
+    /// [`Encoder::write_id`](./struct.Encoder.html#method.write_id).
+    ///
+    /// Ids that were encoded as an index alone can only be decoded within an
+    /// item of an arena that is being decoded, and get that arena's arena id.
+    /// Returns an error if such an id is out of bounds for that arena. Ids
+    /// that were encoded with their arena id get that arena id back.
+    pub fn read_id<A>(&mut self) -> Result<A::Id, DecodeError>
+    where
+        A: ArenaBehavior,
+    {
+        let tagged = self.read_varint()?;
+        let value = tagged >> 1;
+        if tagged & 1 == 1 {
+            let index =
+                usize::try_from(value).map_err(|_| DecodeError::Invalid("id is out of bounds"))?;
+            let arena_id = u32::try_from(self.read_varint()?)
+                .map_err(|_| DecodeError::Invalid("arena id overflows 32 bits"))?;
+            return Ok(A::new_id(arena_id, index));
+        }
+        let item = self
+            .item
+            .ok_or(DecodeError::Invalid("id outside of an arena item"))?;
+        let index = if item.relative_ids {
+            let offset = (value >> 1) as i64 ^ -((value & 1) as i64);
+            let distance = usize::try_from(offset.unsigned_abs()).ok();
+            if offset < 0 {
+                distance.and_then(|distance| item.index.checked_sub(distance))
+            } else {
+                distance.and_then(|distance| item.index.checked_add(distance))
+            }
+        } else {
+            usize::try_from(value).ok()
+        };
+        match index {
+            Some(index) if index < item.len => Ok(A::new_id(item.arena_id, index)),
+            _ => Err(DecodeError::Invalid("id is out of bounds")),
+        }
+    }
+
+    /// Get the bytes that have not been decoded yet.
+    #[inline]
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Check that every byte has been decoded.
+    #[inline]
+    pub fn finish(self) -> Result<(), DecodeError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingBytes {
+                len: self.bytes.len(),
+            })
+        }
+    }
+}
+
+/// The error returned when bytes cannot be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The bytes ended in the middle of a value.
+    UnexpectedEnd,
+
+    /// There were bytes left over after decoding.
+    TrailingBytes {
+        /// The number of bytes left over.
+        len: usize,
+    },
+
+    /// The bytes do not encode a valid value.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::UnexpectedEnd => f.write_str("unexpected end of input"),
+            DecodeError::TrailingBytes { len } => write!(f, "{} trailing bytes", len),
+            DecodeError::Invalid(what) => write!(f, "invalid encoding: {}", what),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+impl Codec for u8 {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u8(*self);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<u8, DecodeError> {
+        decoder.read_u8()
+    }
+}
+
+macro_rules! unsigned_codec {
+    ($($ty:ty),*) => {$(
+        impl Codec for $ty {
+            #[inline]
+            fn encode(&self, encoder: &mut Encoder) {
+                encoder.write_varint(*self as u64);
+            }
+
+            #[inline]
+            fn decode(decoder: &mut Decoder) -> Result<$ty, DecodeError> {
+                <$ty>::try_from(decoder.read_varint()?)
+                    .map_err(|_| DecodeError::Invalid(concat!("out of range for `", stringify!($ty), "`")))
+            }
+        }
+    )*};
+}
+
+unsigned_codec!(u16, u32, u64, usize);
+
+macro_rules! signed_codec {
+    ($($ty:ty),*) => {$(
+        impl Codec for $ty {
+            #[inline]
+            fn encode(&self, encoder: &mut Encoder) {
+                let value = *self as i64;
+                encoder.write_varint(((value << 1) ^ (value >> 63)) as u64);
+            }
+
+            #[inline]
+            fn decode(decoder: &mut Decoder) -> Result<$ty, DecodeError> {
+                let value = decoder.read_varint()?;
+                let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+                <$ty>::try_from(value)
+                    .map_err(|_| DecodeError::Invalid(concat!("out of range for `", stringify!($ty), "`")))
+            }
+        }
+    )*};
+}
+
+signed_codec!(i8, i16, i32, i64, isize);
+
+impl Codec for f32 {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_bytes(&self.to_le_bytes());
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<f32, DecodeError> {
+        let bytes = decoder.read_bytes(4)?;
+        Ok(f32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl Codec for f64 {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_bytes(&self.to_le_bytes());
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<f64, DecodeError> {
+        let bytes = decoder.read_bytes(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+impl Codec for bool {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_u8(*self as u8);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<bool, DecodeError> {
+        match decoder.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Invalid("`bool` is not 0 or 1")),
+        }
+    }
+}
+
+impl Codec for char {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        (*self as u32).encode(encoder);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<char, DecodeError> {
+        char::from_u32(u32::decode(decoder)?)
+            .ok_or(DecodeError::Invalid("not a unicode scalar value"))
+    }
+}
+
+impl Codec for String {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.len().encode(encoder);
+        encoder.write_bytes(self.as_bytes());
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<String, DecodeError> {
+        let len = usize::decode(decoder)?;
+        let bytes = decoder.read_bytes(len)?;
+        let s = core::str::from_utf8(bytes).map_err(|_| DecodeError::Invalid("not UTF-8"))?;
+        Ok(s.into())
+    }
+}
+
+impl<T: Codec> Codec for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.len().encode(encoder);
+        for item in self {
+            item.encode(encoder);
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Vec<T>, DecodeError> {
+        let len = usize::decode(decoder)?;
+        // Every item takes at least one byte, so don't trust a length that is
+        // longer than the remaining input when preallocating.
+        let mut items = Vec::with_capacity(len.min(decoder.remaining().len()));
+        for _ in 0..len {
+            items.push(T::decode(decoder)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            None => encoder.write_u8(0),
+            Some(value) => {
+                encoder.write_u8(1);
+                value.encode(encoder);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Option<T>, DecodeError> {
+        match decoder.read_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(decoder)?)),
+            _ => Err(DecodeError::Invalid("`Option` tag is not 0 or 1")),
+        }
+    }
+}
+
+impl<T: Codec> Codec for Box<T> {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        (**self).encode(encoder);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<Box<T>, DecodeError> {
+        T::decode(decoder).map(Box::new)
+    }
+}
+
+impl<T: Codec, U: Codec> Codec for (T, U) {
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        self.0.encode(encoder);
+        self.1.encode(encoder);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<(T, U), DecodeError> {
+        Ok((T::decode(decoder)?, U::decode(decoder)?))
+    }
+}
+
+impl<T> Codec for Id<T> {
+    /// Encode this id with
+    /// [`Encoder::write_id`](./struct.Encoder.html#method.write_id).
+    #[inline]
+    #[track_caller]
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_id::<DefaultArenaBehavior<T>>(*self);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<Id<T>, DecodeError> {
+        decoder.read_id::<DefaultArenaBehavior<T>>()
+    }
+}
+
+impl<T, A> Codec for Arena<T, A>
+where
+    A: ArenaBehavior,
+    T: Codec,
+{
+    /// Encode this arena's items, with the ids within them encoded as their
+    /// indices.
+    ///
+    /// The decoded arena gets a new arena id, and the ids within its items are
+    /// given that arena id.
+    ///
+    /// ```
+    /// use id_arena::{Arena, Codec, DecodeError, Decoder, Encoder, Id};
+    ///
+    /// struct Node {
+    ///     name: String,
+    ///     parent: Option<Id<Node>>,
+    /// }
+    ///
+    /// impl Codec for Node {
+    ///     fn encode(&self, encoder: &mut Encoder) {
+    ///         self.name.encode(encoder);
+    ///         self.parent.encode(encoder);
+    ///     }
+    ///
+    ///     fn decode(decoder: &mut Decoder) -> Result<Node, DecodeError> {
+    ///         Ok(Node {
+    ///             name: String::decode(decoder)?,
+    ///             parent: Option::decode(decoder)?,
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let mut arena = Arena::<Node>::new();
+    /// let root = arena.alloc(Node { name: "root".into(), parent: None });
+    /// arena.alloc(Node { name: "leaf".into(), parent: Some(root) });
+    ///
+    /// let mut encoder = Encoder::new();
+    /// encoder.set_relative_ids(true);
+    /// arena.encode(&mut encoder);
+    ///
+    /// let decoded = Arena::<Node>::decode(&mut Decoder::new(encoder.as_bytes())).unwrap();
+    /// let (_, leaf) = decoded.iter().find(|(_, node)| node.name == "leaf").unwrap();
+    /// assert_eq!(decoded[leaf.parent.unwrap()].name, "root");
+    /// ```
+    fn encode(&self, encoder: &mut Encoder) {
+        let relative_ids = encoder.relative_ids;
+        relative_ids.encode(encoder);
+        self.items.len().encode(encoder);
+        let outer = encoder.item;
+        for (index, item) in self.items.iter().enumerate() {
+            encoder.item = Some(ItemContext {
+                arena_id: self.arena_id,
+                len: self.items.len(),
+                index,
+                relative_ids,
+            });
+            item.encode(encoder);
+        }
+        encoder.item = outer;
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Arena<T, A>, DecodeError> {
+        let relative_ids = bool::decode(decoder)?;
+        let len = usize::decode(decoder)?;
+        let arena_id = A::new_arena_id();
+        let outer = decoder.item;
+        let mut items = Vec::with_capacity(len.min(decoder.remaining().len()));
+        let result = (0..len).try_for_each(|index| {
+            decoder.item = Some(ItemContext {
+                arena_id,
+                len,
+                index,
+                relative_ids,
+            });
+            items.push(T::decode(decoder)?);
+            Ok(())
+        });
+        decoder.item = outer;
+        result?;
+        Ok(Arena {
+            arena_id,
+            items,
+            _phantom: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use super::*;
+
+/// The differences between two versions of the same arena.
+///
+/// See [the `Arena::diff()` method](./struct.Arena.html#method.diff) for
+/// details.
+pub struct ArenaDiff<'a, T, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    old: &'a [T],
+    new: &'a [T],
+    changed: Vec<usize>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T, A> fmt::Debug for ArenaDiff<'a, T, A>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArenaDiff")
+            .field("arena_id", &self.arena_id)
+            .field("changed", &self.changed)
+            .field("added", &self.new.get(self.old.len()..).unwrap_or(&[]))
+            .field("removed", &self.old.get(self.new.len()..).unwrap_or(&[]))
+            .finish()
+    }
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Compare this arena with a later version of it, such as a clone that was
+    /// modified by an optimization pass.
+    ///
+    /// Returns an error if the two arenas have different arena ids, since their
+    /// ids would not refer to the same objects.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut before = Arena::<&str>::with_name("names");
+    /// let a = before.alloc("Albert");
+    /// let b = before.alloc("Bob");
+    ///
+    /// let mut after = before.clone();
+    /// after[a] = "Alice";
+    /// let c = after.alloc("Carol");
+    ///
+    /// let diff = before.diff(&after).unwrap();
+    /// assert_eq!(diff.changed().map(|(id, _, _)| id).collect::<Vec<_>>(), [a]);
+    /// assert_eq!(diff.added().collect::<Vec<_>>(), [c]);
+    /// assert_eq!(
+    ///     diff.to_string(),
+    ///     "names: 1 changed, 1 added, 0 removed\n\
+    ///      ~ names#0: \"Albert\" -> \"Alice\"\n\
+    ///      + names#2: \"Carol\"\n",
+    /// );
+    /// # let _ = b;
+    /// ```
+    pub fn diff<'a>(&'a self, new: &'a Arena<T, A>) -> Result<ArenaDiff<'a, T, A>, ArenaError>
+    where
+        T: PartialEq,
+    {
+        if self.arena_id != new.arena_id {
+            return Err(ArenaError::WrongArena {
+                expected: self.arena_id,
+                found: new.arena_id,
+                index: None,
+            });
+        }
+        let changed = self
+            .items
+            .iter()
+            .zip(&new.items)
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(index, _)| index)
+            .collect();
+        Ok(ArenaDiff {
+            arena_id: self.arena_id,
+            old: &self.items,
+            new: &new.items,
+            changed,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<'a, T, A> ArenaDiff<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Iterate over the ids whose objects differ between the two versions,
+    /// along with their old and new objects.
+    pub fn changed(&self) -> impl Iterator<Item = (A::Id, &'a T, &'a T)> + '_ {
+        self.changed.iter().map(move |&index| {
+            (
+                A::new_id(self.arena_id, index),
+                &self.old[index],
+                &self.new[index],
+            )
+        })
+    }
+
+    /// Get the ids of the objects that are only in the new version.
+    #[inline]
+    pub fn added(&self) -> IdRange<T, A> {
+        IdRange::new(
+            self.arena_id,
+            self.old.len(),
+            self.new.len().max(self.old.len()),
+        )
+    }
+
+    /// Get the ids of the objects that are only in the old version.
+    ///
+    /// This is only non-empty if the arena that `diff` was called on is the
+    /// longer of the two.
+    #[inline]
+    pub fn removed(&self) -> IdRange<T, A> {
+        IdRange::new(
+            self.arena_id,
+            self.new.len(),
+            self.old.len().max(self.new.len()),
+        )
+    }
+
+    /// Returns `true` if the two versions are equal.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.old.len() == self.new.len()
+    }
+}
+
+impl<'a, T, A> fmt::Display for ArenaDiff<'a, T, A>
+where
+    A: ArenaBehavior,
+    T: fmt::Debug,
+{
+    /// Formats a report with a summary line, followed by one line per changed
+    /// (`~`), added (`+`) and removed (`-`) object.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = ArenaLabel(self.arena_id);
+        writeln!(
+            f,
+            "{}: {} changed, {} added, {} removed",
+            label,
+            self.changed.len(),
+            self.added().len(),
+            self.removed().len(),
+        )?;
+        for &index in &self.changed {
+            writeln!(
+                f,
+                "~ {}#{}: {:?} -> {:?}",
+                label, index, self.old[index], self.new[index]
+            )?;
+        }
+        for index in self.added().indices() {
+            writeln!(f, "+ {}#{}: {:?}", label, index, self.new[index])?;
+        }
+        for index in self.removed().indices() {
+            writeln!(f, "- {}#{}: {:?}", label, index, self.old[index])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,753 @@
+use super::*;
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+/// The size of each entry in the index file: the start and end offsets of an
+/// object in the data file.
+const INDEX_ENTRY_LEN: u64 = 16;
+
+/// The size of each journal record's header: the length and checksum of the
+/// record's payload.
+const RECORD_HEADER_LEN: usize = 8;
+
+/// An append-only arena whose objects are stored in a file instead of memory.
+///
+/// Objects are encoded with their [`Codec`](./trait.Codec.html) implementation
+/// and appended to a data file. Ids of this arena within objects are encoded as
+/// their indices, as with `Arena`'s `Codec` implementation. A second file,
+/// next to the data file and with an `.idx` suffix, records where each object
+/// is stored, so that `get` can read an object without reading any others.
+/// Only these offsets are kept in memory.
+///
+/// Reopening the files with [`open`](#method.open) restores the arena, with
+/// every object at the same index as before, so indices that were saved
+/// elsewhere remain valid across process restarts. To use the very same ids,
+/// reopen the arena with the same arena id, e.g. with
+/// [`open_with_arena_id`](#method.open_with_arena_id).
+///
+/// An arena that is opened with [`open_journaled`](#method.open_journaled)
+/// is in journal mode, and survives crashes in the middle of `alloc` and
+/// `replace`.
+///
+/// When `alloc` or `replace` returns an error, the files are rolled back to
+/// their state before the call. If rolling back fails too, the arena is
+/// poisoned: every `alloc` and `replace` returns an error until a call to
+/// `sync` finishes the roll back.
+///
+/// Objects are read with ordinary file reads rather than through a memory
+/// map, since mapping a file that may be modified by another process is not
+/// memory safe.
+///
+/// ```
+/// use id_arena::DiskArena;
+///
+/// let path = std::env::temp_dir().join(format!("id-arena-doc-{}", std::process::id()));
+///
+/// let mut arena = DiskArena::<String>::open_with_arena_id(&path, 7)?;
+/// let a = arena.alloc("Albert".to_string())?;
+/// drop(arena);
+///
+/// let mut arena = DiskArena::<String>::open_with_arena_id(&path, 7)?;
+/// assert_eq!(arena.get(a)?, "Albert");
+/// let b = arena.alloc("Bob".to_string())?;
+/// assert_eq!(arena.len(), 2);
+/// assert_eq!(arena.get(b)?, "Bob");
+/// # drop(arena);
+/// # std::fs::remove_file(&path)?;
+/// # std::fs::remove_file(path.with_extension("idx"))?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// # Features
+///
+/// This API requires the `persist` feature of this crate to be enabled.
+pub struct DiskArena<T, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    data: Mutex<File>,
+    data_len: u64,
+    index: File,
+    /// The range of bytes in the data file that each object is stored in.
+    spans: Vec<(u64, u64)>,
+    /// The write-ahead log, if this arena is in journal mode.
+    journal: Option<File>,
+    /// A failed write that could not be rolled back yet, as the index it was
+    /// written at and the length of the log before it.
+    poisoned: Option<(usize, Option<u64>)>,
+    encoder: Encoder,
+    #[cfg(test)]
+    fail: Option<tests::FailPoint>,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A> fmt::Debug for DiskArena<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiskArena")
+            .field("arena_id", &self.arena_id)
+            .field("len", &self.spans.len())
+            .field("journaled", &self.journal.is_some())
+            .field("poisoned", &self.poisoned.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// What happened while recovering a journaled `DiskArena` from its log.
+///
+/// See [the `DiskArena::open_journaled()`
+/// method](./struct.DiskArena.html#method.open_journaled) for details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Recovery {
+    /// The number of objects in the arena after recovery.
+    pub len: usize,
+    /// The number of complete log records that were replayed.
+    pub replayed: usize,
+    /// The number of bytes at the end of the log that were discarded because
+    /// they did not form a complete record with a valid checksum.
+    pub discarded_bytes: u64,
+}
+
+/// Get the path of the file with the given suffix that belongs to the data
+/// file at `path`.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut sibling: OsString = path.as_os_str().into();
+    sibling.push(suffix);
+    sibling.into()
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xEDB8_8320
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// The CRC-32 (IEEE) checksum of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Split the first record off of `log`, if it is complete and its checksum
+/// matches, and return its payload and the rest of the log.
+fn split_record(log: &[u8]) -> Option<(&[u8], &[u8])> {
+    let header = log.get(..RECORD_HEADER_LEN)?;
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+    let payload = log.get(RECORD_HEADER_LEN..)?.get(..len)?;
+    if crc32(payload) != checksum {
+        return None;
+    }
+    Some((payload, &log[RECORD_HEADER_LEN + len..]))
+}
+
+impl<T, A> DiskArena<T, A>
+where
+    A: ArenaBehavior,
+    T: Codec,
+{
+    /// Open the arena stored at `path`, creating it if it does not exist yet.
+    ///
+    /// The arena gets a new arena id, as with `Arena::new`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<DiskArena<T, A>> {
+        DiskArena::open_inner(path.as_ref(), A::new_arena_id())
+    }
+
+    /// Open the arena stored at `path`, creating it if it does not exist yet,
+    /// and give it the given arena id.
+    ///
+    /// If an earlier process crashed while allocating into the arena, the
+    /// partially written object is discarded.
+    ///
+    /// Returns an error if the arena has a log that has not been recovered
+    /// yet, which must be opened with `open_journaled`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `arena_id` is not less than `2^31`, as with
+    /// `Arena::with_arena_id`.
+    #[track_caller]
+    pub fn open_with_arena_id(
+        path: impl AsRef<Path>,
+        arena_id: u32,
+    ) -> io::Result<DiskArena<T, A>> {
+        DiskArena::open_inner(path.as_ref(), explicit_arena_id(arena_id))
+    }
+
+    fn open_inner(path: &Path, arena_id: u32) -> io::Result<DiskArena<T, A>> {
+        if fs::metadata(sibling_path(path, ".wal")).map_or(false, |journal| journal.len() > 0) {
+            return Err(invalid_data(
+                "arena has an unrecovered journal, open it with `open_journaled`",
+            ));
+        }
+        let mut arena = DiskArena::open_files(path, arena_id)?;
+        arena.validate()?;
+
+        // Objects are written before their index entry, so a crash can leave
+        // an object without an index entry. It was never returned from
+        // `alloc`, so discard it.
+        let end = arena.spans.iter().map(|&(_, end)| end).max().unwrap_or(0);
+        arena
+            .data
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_len(end)?;
+        arena.data_len = end;
+        Ok(arena)
+    }
+
+    /// Open the arena stored at `path` in journal mode, creating it if it does
+    /// not exist yet.
+    ///
+    /// The arena gets a new arena id, as with `Arena::new`.
+    ///
+    /// See [`open_journaled_with_arena_id`](#method.open_journaled_with_arena_id)
+    /// for details.
+    pub fn open_journaled(path: impl AsRef<Path>) -> io::Result<(DiskArena<T, A>, Recovery)> {
+        DiskArena::open_journaled_inner(path.as_ref(), A::new_arena_id())
+    }
+
+    /// Open the arena stored at `path` in journal mode, creating it if it does
+    /// not exist yet, and give it the given arena id.
+    ///
+    /// In journal mode, every `alloc` and `replace` is first appended to a
+    /// checksummed log next to the data file, with a `.wal` suffix, and only
+    /// then written to the data and index files. Opening the arena replays
+    /// every complete record in the log, and discards a record that was only
+    /// partially written, so the arena is restored to its state after the last
+    /// `alloc` or `replace` that returned.
+    ///
+    /// ```
+    /// use id_arena::DiskArena;
+    ///
+    /// let path = std::env::temp_dir().join(format!("id-arena-wal-doc-{}", std::process::id()));
+    ///
+    /// let (mut arena, _) = DiskArena::<String>::open_journaled_with_arena_id(&path, 7)?;
+    /// let a = arena.alloc("Albert".to_string())?;
+    /// arena.replace(a, "Alice".to_string())?;
+    /// drop(arena);
+    ///
+    /// let (arena, recovery) = DiskArena::<String>::open_journaled_with_arena_id(&path, 7)?;
+    /// assert_eq!(recovery.replayed, 2);
+    /// assert_eq!(recovery.discarded_bytes, 0);
+    /// assert_eq!(arena.get(a)?, "Alice");
+    /// # drop(arena);
+    /// # for suffix in ["", ".idx", ".wal"] {
+    /// #     std::fs::remove_file(format!("{}{}", path.display(), suffix))?;
+    /// # }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `arena_id` is not less than `2^31`, as with
+    /// `Arena::with_arena_id`.
+    #[track_caller]
+    pub fn open_journaled_with_arena_id(
+        path: impl AsRef<Path>,
+        arena_id: u32,
+    ) -> io::Result<(DiskArena<T, A>, Recovery)> {
+        DiskArena::open_journaled_inner(path.as_ref(), explicit_arena_id(arena_id))
+    }
+
+    fn open_journaled_inner(path: &Path, arena_id: u32) -> io::Result<(DiskArena<T, A>, Recovery)> {
+        let mut arena = DiskArena::open_files(path, arena_id)?;
+        let mut journal = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(sibling_path(path, ".wal"))?;
+
+        let mut log = Vec::new();
+        journal.read_to_end(&mut log)?;
+        let mut rest = &log[..];
+        let mut replayed = 0;
+        while let Some((payload, tail)) = split_record(rest) {
+            let mut decoder = Decoder::new(payload);
+            let index = usize::decode(&mut decoder).map_err(invalid_data)?;
+            arena.apply(index, decoder.remaining())?;
+            rest = tail;
+            replayed += 1;
+        }
+        arena.validate()?;
+
+        arena.journal = Some(journal);
+        arena.sync()?;
+        let recovery = Recovery {
+            len: arena.len(),
+            replayed,
+            discarded_bytes: rest.len() as u64,
+        };
+        Ok((arena, recovery))
+    }
+
+    fn open_files(path: &Path, arena_id: u32) -> io::Result<DiskArena<T, A>> {
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(false);
+        let data = options.open(path)?;
+        let mut index = options.open(sibling_path(path, ".idx"))?;
+
+        let mut bytes = Vec::new();
+        index.read_to_end(&mut bytes)?;
+        let spans: Vec<(u64, u64)> = bytes
+            .chunks_exact(INDEX_ENTRY_LEN as usize)
+            .map(|entry| {
+                let start = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                let end = u64::from_le_bytes(entry[8..].try_into().unwrap());
+                (start, end)
+            })
+            .collect();
+        // A crash can leave a partially written entry at the end of the index.
+        index.set_len(spans.len() as u64 * INDEX_ENTRY_LEN)?;
+
+        Ok(DiskArena {
+            arena_id,
+            data_len: data.metadata()?.len(),
+            data: Mutex::new(data),
+            index,
+            spans,
+            journal: None,
+            poisoned: None,
+            encoder: Encoder::new(),
+            #[cfg(test)]
+            fail: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        if self
+            .spans
+            .iter()
+            .any(|&(start, end)| start > end || end > self.data_len)
+        {
+            return Err(invalid_data("index does not match data file"));
+        }
+        Ok(())
+    }
+
+    /// Encode `item` and append it to this arena's file, and return its id.
+    ///
+    /// Returns an error if this arena is poisoned by an earlier failed write.
+    pub fn alloc(&mut self, item: T) -> io::Result<A::Id> {
+        let index = self.spans.len();
+        self.write(index, &item)?;
+        Ok(A::new_id(self.arena_id, index))
+    }
+
+    /// Replace the object associated with the given `id` with `item`.
+    ///
+    /// The new object is appended to the data file, and the space taken by the
+    /// old object is not reused. Outside of journal mode, a crash in the middle
+    /// of `replace` can leave the object's index entry partially written.
+    ///
+    /// Returns an error of kind `InvalidInput` if `id` does not belong to this
+    /// arena, and an error if this arena is poisoned by an earlier failed
+    /// write.
+    pub fn replace(&mut self, id: A::Id, item: T) -> io::Result<()> {
+        let index = self.check_id(id)?;
+        self.write(index, &item)
+    }
+
+    fn write(&mut self, index: usize, item: &T) -> io::Result<()> {
+        if self.poisoned.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "arena is poisoned by a failed write, call `sync` to recover",
+            ));
+        }
+        let journal_len = match &mut self.journal {
+            Some(journal) => Some(journal.stream_position()?),
+            None => None,
+        };
+        let mut encoder = core::mem::take(&mut self.encoder);
+        encoder.clear();
+        encoder.item = Some(ItemContext {
+            arena_id: self.arena_id,
+            len: self.spans.len().max(index + 1),
+            index,
+            relative_ids: false,
+        });
+        item.encode(&mut encoder);
+        let result = self
+            .log(index, encoder.as_bytes())
+            .and_then(|()| self.apply(index, encoder.as_bytes()));
+        self.encoder = encoder;
+        if result.is_err() && self.roll_back(index, journal_len).is_err() {
+            self.poisoned = Some((index, journal_len));
+        }
+        result
+    }
+
+    /// Undo a write at `index` that failed partway, so that the files match
+    /// this arena's state again.
+    ///
+    /// The log is truncated first, so that a write that returned an error is
+    /// never replayed, and later records are not appended after a torn one.
+    fn roll_back(&mut self, index: usize, journal_len: Option<u64>) -> io::Result<()> {
+        #[cfg(test)]
+        if self.fail == Some(tests::FailPoint::ApplyAndRollBack) {
+            return Err(tests::injected());
+        }
+        if let (Some(journal), Some(len)) = (&mut self.journal, journal_len) {
+            journal.set_len(len)?;
+            journal.seek(SeekFrom::Start(len))?;
+            journal.sync_data()?;
+        }
+        self.data
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_len(self.data_len)?;
+        match self.spans.get(index) {
+            Some(&(start, end)) => self.write_index_entry(index, start, end),
+            None => self
+                .index
+                .set_len(self.spans.len() as u64 * INDEX_ENTRY_LEN),
+        }
+    }
+
+    /// Append a record of writing `bytes` at `index` to the log, if this arena
+    /// is in journal mode, and wait for it to reach the disk.
+    fn log(&mut self, index: usize, bytes: &[u8]) -> io::Result<()> {
+        let journal = match &mut self.journal {
+            Some(journal) => journal,
+            None => return Ok(()),
+        };
+        let mut payload = Encoder::new();
+        index.encode(&mut payload);
+        payload.write_bytes(bytes);
+        let payload = payload.into_bytes();
+        let len = u32::try_from(payload.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "object is too large to log")
+        })?;
+
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&crc32(&payload).to_le_bytes());
+        record.extend_from_slice(&payload);
+        #[cfg(test)]
+        if self.fail == Some(tests::FailPoint::Log) {
+            journal.write_all(&record[..record.len() / 2])?;
+            return Err(tests::injected());
+        }
+        journal.write_all(&record)?;
+        journal.sync_data()
+    }
+
+    /// Write `bytes` to the end of the data file, and point the index entry at
+    /// `index` at them, appending a new entry if `index` is this arena's length.
+    fn apply(&mut self, index: usize, bytes: &[u8]) -> io::Result<()> {
+        if index > self.spans.len() {
+            return Err(invalid_data("log record skips over an index"));
+        }
+        let start = self.data_len;
+        let end = start + bytes.len() as u64;
+
+        let data = self.data.get_mut().unwrap_or_else(PoisonError::into_inner);
+        data.seek(SeekFrom::Start(start))?;
+        data.write_all(bytes)?;
+        #[cfg(test)]
+        if matches!(
+            self.fail,
+            Some(tests::FailPoint::Apply | tests::FailPoint::ApplyAndRollBack)
+        ) {
+            return Err(tests::injected());
+        }
+        self.write_index_entry(index, start, end)?;
+
+        // Only update the in-memory state once every write succeeded, so that
+        // a failed write can be rolled back to it.
+        self.data_len = end;
+        if index == self.spans.len() {
+            self.spans.push((start, end));
+        } else {
+            self.spans[index] = (start, end);
+        }
+        Ok(())
+    }
+
+    fn write_index_entry(&mut self, index: usize, start: u64, end: u64) -> io::Result<()> {
+        self.index
+            .seek(SeekFrom::Start(index as u64 * INDEX_ENTRY_LEN))?;
+        self.index.write_all(&start.to_le_bytes())?;
+        self.index.write_all(&end.to_le_bytes())
+    }
+
+    fn check_id(&self, id: A::Id) -> io::Result<usize> {
+        check_id(
+            self.arena_id,
+            self.spans.len(),
+            A::arena_id(id),
+            A::index(id),
+        )
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+
+    /// Read and decode the object associated with the given `id`.
+    ///
+    /// Returns an error of kind `InvalidInput` if `id` does not belong to this
+    /// arena, and of kind `InvalidData` if the object cannot be decoded.
+    pub fn get(&self, id: A::Id) -> io::Result<T> {
+        let index = self.check_id(id)?;
+        let (start, end) = self.spans[index];
+        let mut bytes = std::vec![0; (end - start) as usize];
+        {
+            let mut data = self.data.lock().unwrap_or_else(PoisonError::into_inner);
+            data.seek(SeekFrom::Start(start))?;
+            data.read_exact(&mut bytes)?;
+        }
+
+        let mut decoder = Decoder::new(&bytes);
+        decoder.item = Some(ItemContext {
+            arena_id: self.arena_id,
+            len: self.spans.len(),
+            index,
+            relative_ids: false,
+        });
+        let item = T::decode(&mut decoder).map_err(invalid_data)?;
+        decoder.finish().map_err(invalid_data)?;
+        Ok(item)
+    }
+
+    /// Get the id that will be used for the next object allocated into this
+    /// arena.
+    #[inline]
+    pub fn next_id(&self) -> A::Id {
+        A::new_id(self.arena_id, self.spans.len())
+    }
+
+    /// Get the ids of every object in this arena.
+    #[inline]
+    pub fn ids(&self) -> IdRange<T, A> {
+        IdRange::new(self.arena_id, 0, self.spans.len())
+    }
+
+    /// Flush this arena's files to disk, so that every change made so far
+    /// survives a power failure.
+    ///
+    /// In journal mode, the log's records are no longer needed afterwards, so
+    /// the log is emptied. Otherwise, the log grows with every change.
+    ///
+    /// If this arena is poisoned, the failed write is rolled back first.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if let Some((index, journal_len)) = self.poisoned {
+            self.roll_back(index, journal_len)?;
+            self.poisoned = None;
+        }
+        self.data
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .sync_data()?;
+        self.index.sync_data()?;
+        if let Some(journal) = &mut self.journal {
+            journal.set_len(0)?;
+            journal.seek(SeekFrom::Start(0))?;
+            journal.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where a `DiskArena` write fails, to test that it is rolled back.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(super) enum FailPoint {
+        /// After writing half of the log record.
+        Log,
+        /// After writing the object to the data file.
+        Apply,
+        /// After writing the object to the data file, and when rolling back.
+        ApplyAndRollBack,
+    }
+
+    pub(super) fn injected() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, "injected failure")
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(std::format!("id-arena-{}-{}", name, std::process::id()))
+    }
+
+    fn remove(path: &Path) -> io::Result<()> {
+        for suffix in ["", ".idx", ".wal"] {
+            fs::remove_file(sibling_path(path, suffix))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn torn_log_records_are_truncated() -> io::Result<()> {
+        let path = temp_path("torn-record");
+        let (mut arena, _) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        arena.alloc("a".into())?;
+        arena.fail = Some(FailPoint::Log);
+        assert!(arena.alloc("b".into()).is_err());
+        arena.fail = None;
+        let c = arena.alloc("c".into())?;
+        drop(arena);
+
+        let (arena, recovery) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        assert_eq!(recovery.replayed, 2);
+        assert_eq!(recovery.discarded_bytes, 0);
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.get(c)?, "c");
+        drop(arena);
+        remove(&path)
+    }
+
+    #[test]
+    fn failed_writes_are_not_replayed() -> io::Result<()> {
+        let path = temp_path("failed-apply");
+        let (mut arena, _) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        let a = arena.alloc("a".into())?;
+        arena.fail = Some(FailPoint::Apply);
+        assert!(arena.replace(a, "A".into()).is_err());
+        assert!(arena.alloc("b".into()).is_err());
+        assert_eq!(arena.len(), 1);
+        drop(arena);
+
+        let (arena, recovery) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        assert_eq!(recovery.replayed, 1);
+        assert_eq!(arena.len(), 1);
+        assert_eq!(arena.get(a)?, "a");
+        drop(arena);
+        remove(&path)
+    }
+
+    #[test]
+    fn failed_roll_backs_poison_until_sync() -> io::Result<()> {
+        let path = temp_path("poisoned");
+        let (mut arena, _) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        arena.fail = Some(FailPoint::ApplyAndRollBack);
+        assert!(arena.alloc("a".into()).is_err());
+        arena.fail = None;
+        assert!(arena.alloc("a".into()).is_err());
+
+        arena.sync()?;
+        let a = arena.alloc("a".into())?;
+        drop(arena);
+
+        let (arena, recovery) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        assert_eq!(recovery.discarded_bytes, 0);
+        assert_eq!(arena.len(), 1);
+        assert_eq!(arena.get(a)?, "a");
+        drop(arena);
+        remove(&path)
+    }
+
+    #[test]
+    fn disk_arena_discards_torn_alloc() -> std::io::Result<()> {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(std::format!("id-arena-torn-{}", std::process::id()));
+        let index = std::path::PathBuf::from(std::format!("{}.idx", path.display()));
+        let mut arena = DiskArena::<Vec<u32>>::open_with_arena_id(&path, 1)?;
+        let a = arena.alloc(std::vec![1, 2, 3])?;
+        drop(arena);
+
+        // Simulate a crash after writing part of an object and its index entry.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(&[2, 9])?;
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&index)?
+            .write_all(&[5])?;
+
+        let mut arena = DiskArena::<Vec<u32>>::open_with_arena_id(&path, 1)?;
+        assert_eq!(arena.len(), 1);
+        let b = arena.alloc(std::vec![4])?;
+        assert_eq!(arena.get(a)?, [1, 2, 3]);
+        assert_eq!(arena.get(b)?, [4]);
+        drop(arena);
+
+        std::fs::remove_file(&path)?;
+        std::fs::remove_file(&index)
+    }
+
+    #[test]
+    fn disk_arena_journal_replays_lost_writes() -> std::io::Result<()> {
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(std::format!("id-arena-wal-{}", std::process::id()));
+        let sibling = |suffix| std::format!("{}{}", path.display(), suffix);
+        let (mut arena, recovery) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        assert_eq!(recovery.len, 0);
+        let a = arena.alloc("a".into())?;
+        let b = arena.alloc("b".into())?;
+        arena.replace(a, "A".into())?;
+        drop(arena);
+
+        // An arena with records in its log can only be opened in journal mode.
+        assert!(DiskArena::<String>::open_with_arena_id(&path, 1).is_err());
+
+        // Simulate a crash that lost every write to the data and index files,
+        // and tore the record that was being appended to the log.
+        std::fs::File::create(&path)?;
+        std::fs::File::create(sibling(".idx"))?;
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(sibling(".wal"))?
+            .write_all(&[9, 0, 0, 0, 1, 2])?;
+
+        let (arena, recovery) = DiskArena::<String>::open_journaled_with_arena_id(&path, 1)?;
+        assert_eq!(
+            (recovery.len, recovery.replayed, recovery.discarded_bytes),
+            (2, 3, 6)
+        );
+        assert_eq!(arena.get(a)?, "A");
+        assert_eq!(arena.get(b)?, "b");
+        drop(arena);
+
+        // Recovery empties the log, so the arena can be opened normally again.
+        let arena = DiskArena::<String>::open_with_arena_id(&path, 1)?;
+        assert_eq!(arena.get(a)?, "A");
+        drop(arena);
+
+        for suffix in ["", ".idx", ".wal"] {
+            std::fs::remove_file(sibling(suffix))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use super::*;
+
+/// The error returned when an id cannot be used with an arena.
+///
+/// See [the `Arena::try_get()` method](./struct.Arena.html#method.try_get) for
+/// details.
+///
+/// More variants may be added in the future.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArenaError {
+    /// The id was allocated by a different arena.
+    WrongArena {
+        /// The arena id of the arena that the id was used with.
+        expected: u32,
+        /// The arena id of the id.
+        found: u32,
+        /// The id's index, or `None` if a whole arena was used with another
+        /// arena, e.g. by `Arena::diff`.
+        index: Option<usize>,
+    },
+
+    /// The id's index is not within the arena.
+    OutOfBounds {
+        /// The id's index.
+        index: usize,
+        /// The number of objects in the arena.
+        len: usize,
+    },
+
+    /// The id's object was removed from the arena, e.g. because its allocation
+    /// was undone in a `JournaledArena`.
+    Removed {
+        /// The id's index.
+        index: usize,
+    },
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArenaError::WrongArena {
+                expected,
+                found,
+                index: Some(index),
+            } => write!(
+                f,
+                "id {}#{} belongs to {}, not {}",
+                ArenaLabel(found),
+                index,
+                ArenaLabel(found),
+                ArenaLabel(expected),
+            ),
+            ArenaError::WrongArena {
+                expected,
+                found,
+                index: None,
+            } => write!(
+                f,
+                "{} is not a version of {}",
+                ArenaLabel(found),
+                ArenaLabel(expected),
+            ),
+            ArenaError::OutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+            ArenaError::Removed { index } => {
+                write!(f, "the object at index {} was removed", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ArenaError {}
+
+/// Check that an id with the given arena id and index can be used with an
+/// arena with the given arena id and length.
+#[inline]
+pub(crate) fn check_id(
+    arena_id: u32,
+    len: usize,
+    id_arena_id: u32,
+    index: usize,
+) -> Result<usize, ArenaError> {
+    if id_arena_id != arena_id {
+        Err(ArenaError::WrongArena {
+            expected: arena_id,
+            found: id_arena_id,
+            index: Some(index),
+        })
+    } else if index >= len {
+        Err(ArenaError::OutOfBounds { index, len })
+    } else {
+        Ok(index)
+    }
+}
+
+/// Panic because indexing an arena of `T`s failed.
+#[cold]
+#[track_caller]
+pub(crate) fn index_panic<T>(err: ArenaError) -> ! {
+    panic!(
+        "cannot index arena of `{}`: {}",
+        core::any::type_name::<T>(),
+        err
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(
+        expected = "cannot index arena of `u32`: id types#0 belongs to types, not values"
+    )]
+    fn wrong_arena_panic_names_arenas() {
+        let mut types = Arena::<u32>::with_name("types");
+        let values = Arena::<u32>::with_name("values");
+        let _ = values[types.alloc(0)];
+    }
+}
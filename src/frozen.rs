@@ -0,0 +1,166 @@
+use super::*;
+
+/// A read-only arena that is cheap to clone and share across threads.
+///
+/// See [the `Arena::freeze()` method](./struct.Arena.html#method.freeze) for
+/// details.
+pub struct FrozenArena<T, A = DefaultArenaBehavior<T>> {
+    pub(crate) arena: Arc<Arena<T, A>>,
+}
+
+impl<T, A> fmt::Debug for FrozenArena<T, A>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrozenArena")
+            .field("arena_id", &self.arena.arena_id)
+            .field("items", &self.arena.items)
+            .finish()
+    }
+}
+
+impl<T, A> Clone for FrozenArena<T, A> {
+    #[inline]
+    fn clone(&self) -> FrozenArena<T, A> {
+        FrozenArena {
+            arena: self.arena.clone(),
+        }
+    }
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Freeze this arena, making it read-only and cheap to clone.
+    ///
+    /// Clones of a `FrozenArena` share the same items, so it can be handed to
+    /// many threads without copying. The frozen arena keeps this arena's arena
+    /// id, so existing ids remain valid.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    /// use std::thread;
+    ///
+    /// let mut arena = Arena::<&str>::new();
+    /// let a = arena.alloc("Albert");
+    ///
+    /// let frozen = arena.freeze();
+    /// let handles: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let frozen = frozen.clone();
+    ///         thread::spawn(move || assert_eq!(frozen[a], "Albert"))
+    ///     })
+    ///     .collect();
+    /// for handle in handles {
+    ///     handle.join().unwrap();
+    /// }
+    ///
+    /// let mut arena = frozen.thaw();
+    /// arena[a] = "Alice";
+    /// ```
+    #[inline]
+    pub fn freeze(self) -> FrozenArena<T, A> {
+        FrozenArena {
+            arena: Arc::new(self),
+        }
+    }
+}
+
+impl<T, A> FrozenArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Turn this frozen arena back into a mutable `Arena` with the same arena
+    /// id.
+    ///
+    /// The items are only cloned if other clones of this frozen arena are
+    /// still alive.
+    pub fn thaw(self) -> Arena<T, A>
+    where
+        T: Clone,
+    {
+        Arc::try_unwrap(self.arena).unwrap_or_else(|shared| Arena {
+            arena_id: shared.arena_id,
+            items: shared.items.clone(),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        self.arena.get(id)
+    }
+
+    /// Get a shared reference to the object associated with the given `id`, or
+    /// an error describing why there is none.
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&T, ArenaError> {
+        self.arena.try_get(id)
+    }
+
+    /// Iterate over this arena's items and their ids.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        self.arena.iter()
+    }
+
+    /// Get the number of objects in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns `true` if this arena contains no objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+}
+
+impl<T, A> ops::Index<A::Id> for FrozenArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        &self.arena[id]
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a FrozenArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+    type IntoIter = Iter<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, T, A> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thawing_shared_frozen_arena_keeps_ids() {
+        let mut arena = Arena::<u32>::new();
+        let id = arena.alloc(1);
+        let frozen = arena.freeze();
+        let shared = frozen.clone();
+
+        let mut thawed = frozen.thaw();
+        thawed[id] = 2;
+        assert_eq!(shared[id], 1);
+        assert_eq!(shared.thaw()[id], 1);
+    }
+}
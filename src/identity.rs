@@ -0,0 +1,339 @@
+use super::*;
+
+/// A persistent 128-bit identity for an arena.
+///
+/// An arena id is only meaningful within the process that created it, so ids
+/// that are written to disk or sent to another process cannot carry it.
+/// Instead, give the arena an `ArenaIdentity` that is stored alongside its
+/// items, and serialize its ids as [`StableId`](./struct.StableId.html)s.
+/// After loading, an [`IdentityRegistry`](./struct.IdentityRegistry.html)
+/// resolves them back to ids of the loaded arena.
+///
+/// Identities are formatted like UUIDs:
+///
+/// ```
+/// use id_arena::ArenaIdentity;
+///
+/// let identity = ArenaIdentity::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+/// assert_eq!(identity.to_string(), "01234567-89ab-cdef-0123-456789abcdef");
+///
+/// // Identities generated from the same seed are the same.
+/// assert_eq!(ArenaIdentity::from_seed(42), ArenaIdentity::from_seed(42));
+/// assert_ne!(ArenaIdentity::from_seed(42), ArenaIdentity::from_seed(43));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArenaIdentity(u128);
+
+impl ArenaIdentity {
+    /// Construct an identity from its 128-bit value.
+    #[inline]
+    pub const fn from_u128(value: u128) -> ArenaIdentity {
+        ArenaIdentity(value)
+    }
+
+    /// Generate an identity from a 64-bit `seed`.
+    ///
+    /// The seed is mixed so that nearby seeds, such as a counter or a
+    /// timestamp, give unrelated identities. The same seed always gives the
+    /// same identity.
+    pub const fn from_seed(seed: u64) -> ArenaIdentity {
+        const fn splitmix64(state: u64) -> u64 {
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+        const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+        let high = splitmix64(seed.wrapping_add(GAMMA));
+        let low = splitmix64(seed.wrapping_add(GAMMA.wrapping_mul(2)));
+        ArenaIdentity(((high as u128) << 64) | low as u128)
+    }
+
+    /// Get this identity's 128-bit value.
+    #[inline]
+    pub const fn as_u128(self) -> u128 {
+        self.0
+    }
+}
+
+impl fmt::Display for ArenaIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let v = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            v >> 96,
+            (v >> 80) & 0xffff,
+            (v >> 64) & 0xffff,
+            (v >> 48) & 0xffff,
+            v & 0xffff_ffff_ffff
+        )
+    }
+}
+
+impl Codec for ArenaIdentity {
+    /// Encode this identity as 16 little-endian bytes.
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_bytes(&self.0.to_le_bytes());
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<ArenaIdentity, DecodeError> {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(decoder.read_bytes(16)?);
+        Ok(ArenaIdentity(u128::from_le_bytes(bytes)))
+    }
+}
+
+/// An id that can be serialized and resolved in another process: the
+/// [`ArenaIdentity`](./struct.ArenaIdentity.html) of its arena, and its index.
+///
+/// Use an [`IdentityRegistry`](./struct.IdentityRegistry.html) to convert
+/// between ids and stable ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StableId {
+    identity: ArenaIdentity,
+    index: usize,
+}
+
+impl StableId {
+    /// Construct a stable id for the object at `index` in the arena with the
+    /// given `identity`.
+    #[inline]
+    pub const fn new(identity: ArenaIdentity, index: usize) -> StableId {
+        StableId { identity, index }
+    }
+
+    /// Get the identity of this id's arena.
+    #[inline]
+    pub const fn identity(self) -> ArenaIdentity {
+        self.identity
+    }
+
+    /// Get the index of this id's object within its arena.
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}#{}", self.identity, self.index)
+    }
+}
+
+impl Codec for StableId {
+    /// Encode this id's arena identity, followed by its index as a varint.
+    #[inline]
+    fn encode(&self, encoder: &mut Encoder) {
+        self.identity.encode(encoder);
+        self.index.encode(encoder);
+    }
+
+    #[inline]
+    fn decode(decoder: &mut Decoder) -> Result<StableId, DecodeError> {
+        Ok(StableId {
+            identity: ArenaIdentity::decode(decoder)?,
+            index: usize::decode(decoder)?,
+        })
+    }
+}
+
+/// A registry of which in-memory arena has which
+/// [`ArenaIdentity`](./struct.ArenaIdentity.html), for converting between ids
+/// and [`StableId`](./struct.StableId.html)s.
+///
+/// ```
+/// use id_arena::{Arena, ArenaIdentity, Codec, Decoder, Encoder, IdentityRegistry, StableId};
+///
+/// let identity = ArenaIdentity::from_seed(7);
+///
+/// // Save an arena and a stable id that refers into it.
+/// let mut arena = Arena::<String>::new();
+/// arena.alloc("a".to_string());
+/// let b = arena.alloc("b".to_string());
+///
+/// let mut registry = IdentityRegistry::new();
+/// registry.register(identity, &arena);
+///
+/// let mut encoder = Encoder::new();
+/// identity.encode(&mut encoder);
+/// arena.encode(&mut encoder);
+/// registry.stable_id(b).unwrap().encode(&mut encoder);
+///
+/// // Load them again, as another process would.
+/// let mut decoder = Decoder::new(encoder.as_bytes());
+/// let identity = ArenaIdentity::decode(&mut decoder).unwrap();
+/// let loaded = Arena::<String>::decode(&mut decoder).unwrap();
+/// let stable = StableId::decode(&mut decoder).unwrap();
+///
+/// let mut registry = IdentityRegistry::new();
+/// registry.register(identity, &loaded);
+/// let b = registry.resolve(stable).unwrap();
+/// assert_eq!(loaded[b], "b");
+/// ```
+pub struct IdentityRegistry<T, A = DefaultArenaBehavior<T>> {
+    /// The arena id of each identity's arena, and the arena's length when it
+    /// was registered.
+    by_identity: BTreeMap<ArenaIdentity, (u32, usize)>,
+    by_arena_id: BTreeMap<u32, ArenaIdentity>,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A> fmt::Debug for IdentityRegistry<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IdentityRegistry")
+            .field("by_identity", &self.by_identity)
+            .finish()
+    }
+}
+
+impl<T, A> Clone for IdentityRegistry<T, A> {
+    fn clone(&self) -> Self {
+        IdentityRegistry {
+            by_identity: self.by_identity.clone(),
+            by_arena_id: self.by_arena_id.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Default for IdentityRegistry<T, A> {
+    #[inline]
+    fn default() -> Self {
+        IdentityRegistry {
+            by_identity: BTreeMap::new(),
+            by_arena_id: BTreeMap::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> IdentityRegistry<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `IdentityRegistry`.
+    #[inline]
+    pub fn new() -> IdentityRegistry<T, A> {
+        Default::default()
+    }
+
+    /// Register `arena` as the arena with the given `identity`.
+    ///
+    /// This replaces any arena that was registered with `identity` before,
+    /// and any identity that `arena` was registered with before. Returns
+    /// `true` if another arena was registered with `identity`.
+    ///
+    /// Only the objects that `arena` has now can be resolved, so register it
+    /// again after allocating more objects that stable ids may refer to.
+    #[inline]
+    pub fn register(&mut self, identity: ArenaIdentity, arena: &Arena<T, A>) -> bool {
+        self.register_arena_id(identity, arena.arena_id, arena.len())
+    }
+
+    /// Register the arena that `id` belongs to, which has `len` objects, as
+    /// the arena with the given `identity`.
+    ///
+    /// This is like `register`, for arenas other than `Arena`, such as a
+    /// `DiskArena` or an `ArchivedArena`.
+    #[inline]
+    pub fn register_arena_of(&mut self, identity: ArenaIdentity, id: A::Id, len: usize) -> bool {
+        self.register_arena_id(identity, A::arena_id(id), len)
+    }
+
+    fn register_arena_id(&mut self, identity: ArenaIdentity, arena_id: u32, len: usize) -> bool {
+        if let Some(old_identity) = self.by_arena_id.insert(arena_id, identity) {
+            self.by_identity.remove(&old_identity);
+        }
+        match self.by_identity.insert(identity, (arena_id, len)) {
+            Some((old_arena_id, _)) if old_arena_id != arena_id => {
+                self.by_arena_id.remove(&old_arena_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remove the arena with the given `identity` from this registry, and
+    /// return `true` if it was registered.
+    pub fn unregister(&mut self, identity: ArenaIdentity) -> bool {
+        match self.by_identity.remove(&identity) {
+            Some((arena_id, _)) => {
+                self.by_arena_id.remove(&arena_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Convert `id` into a stable id, if its arena is registered.
+    #[inline]
+    pub fn stable_id(&self, id: A::Id) -> Option<StableId> {
+        let identity = *self.by_arena_id.get(&A::arena_id(id))?;
+        Some(StableId::new(identity, A::index(id)))
+    }
+
+    /// Convert `stable` back into an id of the arena that is registered with
+    /// its identity, if any.
+    ///
+    /// Returns `None` if no arena is registered with the identity, or if the
+    /// index is not less than the arena's length when it was registered, so
+    /// stable ids from untrusted data never resolve to ids of missing objects.
+    #[inline]
+    pub fn resolve(&self, stable: StableId) -> Option<A::Id> {
+        let (arena_id, len) = *self.by_identity.get(&stable.identity)?;
+        if stable.index >= len {
+            return None;
+        }
+        Some(A::new_id(arena_id, stable.index))
+    }
+
+    /// Get the number of registered arenas.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.by_identity.len()
+    }
+
+    /// Returns `true` if no arenas are registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.by_identity.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_registry_rebinds_identities() {
+        let (x, y) = (ArenaIdentity::from_seed(1), ArenaIdentity::from_seed(2));
+        let mut old = Arena::<u32>::new();
+        let id = old.alloc(0);
+        let mut new = Arena::<u32>::new();
+
+        let mut registry = IdentityRegistry::new();
+        assert!(!registry.register(x, &old));
+        let stable = registry.stable_id(id).unwrap();
+        assert_eq!(stable, StableId::new(x, 0));
+
+        // Registering a new arena with the identity replaces the old arena.
+        assert!(registry.register(x, &new));
+        assert_eq!(registry.stable_id(id), None);
+        assert_eq!(registry.resolve(stable), None);
+        let new_id = new.alloc(1);
+        registry.register(x, &new);
+        assert_eq!(registry.resolve(stable), Some(new_id));
+        assert_eq!(registry.resolve(StableId::new(x, 1)), None);
+
+        // Registering the arena with another identity replaces its identity.
+        registry.register(y, &new);
+        assert_eq!(registry.resolve(stable), None);
+        assert_eq!(registry.len(), 1);
+        assert!(registry.unregister(y));
+        assert!(!registry.unregister(y));
+        assert!(registry.is_empty());
+    }
+}
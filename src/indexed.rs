@@ -0,0 +1,394 @@
+use super::*;
+
+use core::any::Any;
+use core::borrow::Borrow;
+
+/// An arena that maintains secondary indices over its objects.
+///
+/// Each index is registered with a function that extracts a key from an
+/// object, and maps every key to the ids of the objects with that key, in
+/// order of their indices. Indices are updated when objects are allocated, and
+/// when they are mutated through `modify`, which is the only way to get
+/// exclusive access to an object.
+///
+/// ```
+/// use id_arena::IndexedArena;
+///
+/// struct Node {
+///     name: String,
+///     size: u32,
+/// }
+///
+/// let mut arena = IndexedArena::<Node>::new();
+/// let by_name = arena.register_index(|node| node.name.clone());
+///
+/// let a = arena.alloc(Node { name: "x".into(), size: 1 });
+/// let b = arena.alloc(Node { name: "y".into(), size: 2 });
+/// let c = arena.alloc(Node { name: "x".into(), size: 3 });
+///
+/// // Indices registered later include the existing objects too.
+/// let by_size = arena.register_index(|node| node.size);
+///
+/// assert_eq!(arena.lookup(by_name, "x"), [a, c]);
+/// assert_eq!(arena.lookup(by_size, &2), [b]);
+///
+/// arena.modify(a, |node| node.name = "y".into());
+/// assert_eq!(arena.lookup(by_name, "x"), [c]);
+/// assert_eq!(arena.lookup(by_name, "y"), [a, b]);
+/// assert!(arena.lookup(by_name, "z").is_empty());
+/// ```
+pub struct IndexedArena<T, A = DefaultArenaBehavior<T>>
+where
+    A: ArenaBehavior,
+{
+    arena: Arena<T, A>,
+    indices: Vec<Box<dyn ErasedIndex<T, A::Id>>>,
+}
+
+/// A secondary index whose key type has been erased, so that indices with
+/// different key types can be stored together.
+trait ErasedIndex<T, I>: Send + Sync {
+    fn insert(&mut self, item: &T, id: I);
+    fn remove(&mut self, item: &T, id: I);
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct KeyIndex<T, K, A>
+where
+    A: ArenaBehavior,
+{
+    key: Box<dyn Fn(&T) -> K + Send + Sync>,
+    map: BTreeMap<K, Vec<A::Id>>,
+}
+
+impl<T, K, A> ErasedIndex<T, A::Id> for KeyIndex<T, K, A>
+where
+    T: 'static,
+    K: Ord + Send + Sync + 'static,
+    A: ArenaBehavior + 'static,
+    A::Id: Send + Sync,
+{
+    fn insert(&mut self, item: &T, id: A::Id) {
+        let ids = self.map.entry((self.key)(item)).or_default();
+        let position = ids
+            .binary_search_by_key(&A::index(id), |&id| A::index(id))
+            .unwrap_or_else(|position| position);
+        ids.insert(position, id);
+    }
+
+    fn remove(&mut self, item: &T, id: A::Id) {
+        let key = (self.key)(item);
+        if let Some(ids) = self.map.get_mut(&key) {
+            if let Ok(position) = ids.binary_search_by_key(&A::index(id), |&id| A::index(id)) {
+                ids.remove(position);
+            }
+            if ids.is_empty() {
+                self.map.remove(&key);
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T, A> fmt::Debug for IndexedArena<T, A>
+where
+    A: ArenaBehavior,
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IndexedArena")
+            .field("arena_id", &self.arena.arena_id)
+            .field("items", &self.arena.items)
+            .field("indices", &self.indices.len())
+            .finish()
+    }
+}
+
+impl<T, A> Default for IndexedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn default() -> IndexedArena<T, A> {
+        Arena::new().into()
+    }
+}
+
+impl<T, A> From<Arena<T, A>> for IndexedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Start maintaining indices over the objects in `arena`.
+    #[inline]
+    fn from(arena: Arena<T, A>) -> IndexedArena<T, A> {
+        IndexedArena {
+            arena,
+            indices: Vec::new(),
+        }
+    }
+}
+
+impl<T, A> IndexedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `IndexedArena`.
+    #[inline]
+    pub fn new() -> IndexedArena<T, A> {
+        Default::default()
+    }
+
+    /// Register a new index that maps the key extracted from each object by
+    /// `key` to the ids of the objects with that key, and return a handle for
+    /// looking ids up in it.
+    ///
+    /// The index is built from the objects already in this arena.
+    pub fn register_index<K, F>(&mut self, key: F) -> IndexHandle<K>
+    where
+        T: 'static,
+        K: Ord + Send + Sync + 'static,
+        A: 'static,
+        A::Id: Send + Sync,
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        let mut index = KeyIndex::<T, K, A> {
+            key: Box::new(key),
+            map: BTreeMap::new(),
+        };
+        for (id, item) in self.arena.iter() {
+            index.insert(item, id);
+        }
+        self.indices.push(Box::new(index));
+        IndexHandle {
+            arena_id: self.arena.arena_id,
+            index: self.indices.len() - 1,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get the ids of the objects whose key in the index of `handle` is `key`,
+    /// in order of their indices.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `handle` was not returned from this arena's `register_index`.
+    #[track_caller]
+    pub fn lookup<K, Q>(&self, handle: IndexHandle<K>, key: &Q) -> &[A::Id]
+    where
+        T: 'static,
+        K: Ord + Borrow<Q> + 'static,
+        Q: Ord + ?Sized,
+        A: 'static,
+    {
+        let index = self
+            .indices
+            .get(handle.index)
+            .filter(|_| handle.arena_id == self.arena.arena_id)
+            .and_then(|index| index.as_any().downcast_ref::<KeyIndex<T, K, A>>())
+            .expect("cannot look up key: index handle belongs to another arena");
+        index.map.get(key).map_or(&[], |ids| &ids[..])
+    }
+
+    /// Allocate `item` within this arena, add it to every index, and return
+    /// its id.
+    #[inline]
+    pub fn alloc(&mut self, item: T) -> A::Id {
+        let id = self.arena.alloc(item);
+        let item = &self.arena.items[A::index(id)];
+        for index in &mut self.indices {
+            index.insert(item, id);
+        }
+        id
+    }
+
+    /// Get the id that will be used for the next item allocated into this
+    /// arena.
+    #[inline]
+    pub fn next_id(&self) -> A::Id {
+        self.arena.next_id()
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        self.arena.get(id)
+    }
+
+    /// Mutate the object associated with the given `id` with `f`, update every
+    /// index with its new keys, and return the result of `f`.
+    ///
+    /// If `f` panics, the indices are still updated with the keys of the
+    /// object as `f` left it.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no object associated with `id` in this arena.
+    #[track_caller]
+    pub fn modify<F, R>(&mut self, id: A::Id, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let item = &self.arena[id];
+        for index in &mut self.indices {
+            index.remove(item, id);
+        }
+        let guard = Reinsert { arena: self, id };
+        f(&mut guard.arena.arena.items[A::index(id)])
+    }
+
+    /// Iterate over this arena's items and their ids.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        self.arena.iter()
+    }
+
+    /// Get a shared reference to the underlying arena.
+    #[inline]
+    pub fn as_arena(&self) -> &Arena<T, A> {
+        &self.arena
+    }
+
+    /// Drop the indices and return the underlying arena.
+    #[inline]
+    pub fn into_inner(self) -> Arena<T, A> {
+        self.arena
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+}
+
+/// Reinserts an object into every index when dropped, even if the function
+/// that `IndexedArena::modify` called with it panicked.
+struct Reinsert<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    arena: &'a mut IndexedArena<T, A>,
+    id: A::Id,
+}
+
+impl<T, A> Drop for Reinsert<'_, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn drop(&mut self) {
+        let IndexedArena { arena, indices } = &mut *self.arena;
+        let item = &arena.items[A::index(self.id)];
+        for index in indices {
+            index.insert(item, self.id);
+        }
+    }
+}
+
+impl<T, A> ops::Index<A::Id> for IndexedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        &self.arena[id]
+    }
+}
+
+/// A handle to one of an `IndexedArena`'s indices, whose keys are of type
+/// `K`.
+///
+/// See [the `IndexedArena::register_index()`
+/// method](./struct.IndexedArena.html#method.register_index) for details.
+pub struct IndexHandle<K> {
+    arena_id: u32,
+    index: usize,
+    _phantom: PhantomData<fn() -> K>,
+}
+
+impl<K> fmt::Debug for IndexHandle<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IndexHandle")
+            .field("arena_id", &self.arena_id)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl<K> Copy for IndexHandle<K> {}
+
+impl<K> Clone for IndexHandle<K> {
+    #[inline]
+    fn clone(&self) -> IndexHandle<K> {
+        *self
+    }
+}
+
+impl<K> PartialEq for IndexHandle<K> {
+    #[inline]
+    fn eq(&self, rhs: &Self) -> bool {
+        self.arena_id == rhs.arena_id && self.index == rhs.index
+    }
+}
+
+impl<K> Eq for IndexHandle<K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_arena_keeps_ids_sorted_after_modify() {
+        let mut arena = IndexedArena::<u32>::new();
+        let parity = arena.register_index(|&x| x % 2);
+        let ids: Vec<_> = (0..6).map(|x| arena.alloc(x)).collect();
+        arena.modify(ids[4], |x| *x += 1);
+        arena.modify(ids[1], |x| *x += 2);
+        assert_eq!(arena.lookup(parity, &0), [ids[0], ids[2]]);
+        assert_eq!(arena.lookup(parity, &1), [ids[1], ids[3], ids[4], ids[5]]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn indexed_arena_stays_consistent_when_modify_panics() {
+        let mut arena = IndexedArena::<u32>::new();
+        let parity = arena.register_index(|&x| x % 2);
+        let a = arena.alloc(0);
+        let b = arena.alloc(2);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            arena.modify(a, |x| {
+                *x += 1;
+                panic!("oops");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(arena.lookup(parity, &0), [b]);
+        assert_eq!(arena.lookup(parity, &1), [a]);
+    }
+
+    #[test]
+    fn indexed_arenas_are_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<IndexedArena<u32>>();
+    }
+
+    #[test]
+    #[should_panic(expected = "index handle belongs to another arena")]
+    fn indexed_arena_rejects_foreign_handles() {
+        let mut other = IndexedArena::<u32>::new();
+        let handle = other.register_index(|&x| x);
+        let _ = IndexedArena::<u32>::new().lookup(handle, &0);
+    }
+}
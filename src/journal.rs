@@ -0,0 +1,440 @@
+use super::*;
+
+/// An operation on a `JournaledArena`'s items, recorded so that it can be
+/// applied later to undo or redo a change.
+#[derive(Debug)]
+enum Op<T> {
+    /// Restore the removed item at the given index.
+    Restore(usize),
+    /// Remove the item at the given index.
+    Remove(usize),
+    /// Replace the item at the given index.
+    Replace(usize, T),
+}
+
+impl<T> Op<T> {
+    /// Apply this operation to `items` and the set of `removed` indices, and
+    /// return the operation that undoes it.
+    fn apply(self, items: &mut [T], removed: &mut BTreeSet<usize>) -> Op<T> {
+        match self {
+            Op::Restore(index) => {
+                removed.remove(&index);
+                Op::Remove(index)
+            }
+            Op::Remove(index) => {
+                removed.insert(index);
+                Op::Restore(index)
+            }
+            Op::Replace(index, item) => {
+                Op::Replace(index, core::mem::replace(&mut items[index], item))
+            }
+        }
+    }
+}
+
+/// An arena that records its allocations and replacements, so that they can
+/// be undone and redone.
+///
+/// Objects can only be changed with `replace`, so that every change is
+/// recorded. Consecutive changes can be grouped into one undoable step with
+/// `begin_transaction` and `commit_transaction`.
+///
+/// Undoing an allocation removes the object from the arena, and its id becomes
+/// invalid until the allocation is redone. Ids are never reused, so once a new
+/// change discards the redo history, the ids of undone allocations stay
+/// invalid, and `try_get` returns `ArenaError::Removed` for them. The removed
+/// objects are kept in the underlying arena.
+///
+/// ```
+/// use id_arena::JournaledArena;
+///
+/// let mut doc = JournaledArena::<&str>::new();
+/// let title = doc.alloc("Untitled");
+///
+/// doc.begin_transaction();
+/// doc.replace(title, "Draft");
+/// let body = doc.alloc("Hello");
+/// doc.commit_transaction();
+///
+/// assert!(doc.undo());
+/// assert_eq!(doc[title], "Untitled");
+/// assert!(doc.get(body).is_none());
+///
+/// assert!(doc.redo());
+/// assert_eq!(doc[title], "Draft");
+/// assert_eq!(doc[body], "Hello");
+/// ```
+pub struct JournaledArena<T, A = DefaultArenaBehavior<T>> {
+    arena: Arena<T, A>,
+    /// The indices of the objects whose allocation has been undone.
+    removed: BTreeSet<usize>,
+    /// Each step's operations are stored in the order they are applied in.
+    undo: Vec<Vec<Op<T>>>,
+    redo: Vec<Vec<Op<T>>>,
+    transaction: Vec<Op<T>>,
+    depth: usize,
+}
+
+impl<T, A> fmt::Debug for JournaledArena<T, A>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JournaledArena")
+            .field("arena_id", &self.arena.arena_id)
+            .field("items", &self.arena.items)
+            .field("removed", &self.removed)
+            .field("undo", &self.undo)
+            .field("redo", &self.redo)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, A> Default for JournaledArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn default() -> JournaledArena<T, A> {
+        Arena::new().into()
+    }
+}
+
+impl<T, A> From<Arena<T, A>> for JournaledArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Start recording changes to `arena`, with an empty history.
+    fn from(arena: Arena<T, A>) -> JournaledArena<T, A> {
+        JournaledArena {
+            arena,
+            removed: BTreeSet::new(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            transaction: Vec::new(),
+            depth: 0,
+        }
+    }
+}
+
+impl<T, A> JournaledArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `JournaledArena`.
+    #[inline]
+    pub fn new() -> JournaledArena<T, A> {
+        Default::default()
+    }
+
+    /// Allocate `item` within this arena and return its id.
+    pub fn alloc(&mut self, item: T) -> A::Id {
+        let id = self.arena.alloc(item);
+        self.record(Op::Remove(A::index(id)));
+        id
+    }
+
+    /// Replace the object associated with the given `id` with `item`, and
+    /// return the old object.
+    ///
+    /// The old object is cloned, so that the history keeps a copy of it.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no object associated with `id` in this arena.
+    #[track_caller]
+    pub fn replace(&mut self, id: A::Id, item: T) -> T
+    where
+        T: Clone,
+    {
+        if let Err(err) = self.try_get(id) {
+            index_panic::<T>(err);
+        }
+        let old = core::mem::replace(&mut self.arena.items[A::index(id)], item);
+        self.record(Op::Replace(A::index(id), old.clone()));
+        old
+    }
+
+    /// Start grouping the following allocations and replacements into a
+    /// single step for `undo` and `redo`.
+    ///
+    /// Transactions may be nested, in which case they are grouped into the
+    /// outermost transaction.
+    #[inline]
+    pub fn begin_transaction(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Finish the innermost transaction started with `begin_transaction`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no transaction in progress.
+    #[track_caller]
+    pub fn commit_transaction(&mut self) {
+        assert!(self.depth > 0, "no transaction in progress");
+        self.depth -= 1;
+        if self.depth == 0 && !self.transaction.is_empty() {
+            // The inverse operations must be applied in the opposite order to
+            // the changes that they undo.
+            let mut ops = core::mem::take(&mut self.transaction);
+            ops.reverse();
+            self.undo.push(ops);
+        }
+    }
+
+    /// Undo the last step, and return `true` if there was one to undo.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if a transaction is in progress.
+    #[track_caller]
+    pub fn undo(&mut self) -> bool {
+        assert!(self.depth == 0, "cannot undo during a transaction");
+        match self.undo.pop() {
+            Some(ops) => {
+                let inverse = self.apply(ops);
+                self.redo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last undone step, and return `true` if there was one to redo.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if a transaction is in progress.
+    #[track_caller]
+    pub fn redo(&mut self) -> bool {
+        assert!(self.depth == 0, "cannot redo during a transaction");
+        match self.redo.pop() {
+            Some(ops) => {
+                let inverse = self.apply(ops);
+                self.undo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` if there is a step to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is a step to redo.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        self.try_get(id).ok()
+    }
+
+    /// Get a shared reference to the object associated with the given `id`, or
+    /// an error describing why there is none.
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&T, ArenaError> {
+        let item = self.arena.try_get(id)?;
+        if self.removed.contains(&A::index(id)) {
+            return Err(ArenaError::Removed {
+                index: A::index(id),
+            });
+        }
+        Ok(item)
+    }
+
+    /// Iterate over this arena's items and their ids, skipping removed
+    /// objects.
+    #[inline]
+    pub fn iter(&self) -> JournaledIter<'_, T, A> {
+        JournaledIter {
+            iter: self.arena.iter(),
+            removed: &self.removed,
+        }
+    }
+
+    /// Get a shared reference to the underlying arena.
+    ///
+    /// The underlying arena still contains the objects whose allocation has
+    /// been undone.
+    #[inline]
+    pub fn as_arena(&self) -> &Arena<T, A> {
+        &self.arena
+    }
+
+    /// Discard the history and return the underlying arena.
+    ///
+    /// The underlying arena still contains the objects whose allocation has
+    /// been undone.
+    #[inline]
+    pub fn into_inner(self) -> Arena<T, A> {
+        self.arena
+    }
+
+    /// Get the number of objects in this arena, not counting removed objects.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.arena.len() - self.removed.len()
+    }
+
+    /// Returns `true` if there are no objects in this arena, not counting
+    /// removed objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record `inverse`, the operation that undoes a change that was just made.
+    fn record(&mut self, inverse: Op<T>) {
+        self.redo.clear();
+        if self.depth > 0 {
+            self.transaction.push(inverse);
+        } else {
+            self.undo.push(Vec::from([inverse]));
+        }
+    }
+
+    /// Apply the operations of one step in order, and return the step that
+    /// undoes them.
+    fn apply(&mut self, ops: Vec<Op<T>>) -> Vec<Op<T>> {
+        let mut inverse: Vec<_> = ops
+            .into_iter()
+            .map(|op| op.apply(&mut self.arena.items, &mut self.removed))
+            .collect();
+        inverse.reverse();
+        inverse
+    }
+}
+
+impl<T, A> ops::Index<A::Id> for JournaledArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        match self.try_get(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+/// An iterator over `(Id, &T)` pairs in a `JournaledArena`, which skips removed
+/// objects.
+///
+/// See [the `JournaledArena::iter()`
+/// method](./struct.JournaledArena.html#method.iter) for details.
+#[derive(Debug)]
+pub struct JournaledIter<'a, T: 'a, A: 'a> {
+    iter: Iter<'a, T, A>,
+    removed: &'a BTreeSet<usize>,
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for JournaledIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let removed = self.removed;
+        self.iter.find(|&(id, _)| !removed.contains(&A::index(id)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for JournaledIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let removed = self.removed;
+        self.iter.rfind(|&(id, _)| !removed.contains(&A::index(id)))
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a JournaledArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+    type IntoIter = JournaledIter<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> JournaledIter<'a, T, A> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_undoes_and_redoes_in_order() {
+        let mut arena = JournaledArena::<u32>::new();
+        let a = arena.alloc(0);
+        arena.begin_transaction();
+        arena.replace(a, 1);
+        arena.begin_transaction();
+        let b = arena.alloc(10);
+        arena.replace(b, 11);
+        arena.commit_transaction();
+        arena.replace(a, 2);
+        arena.commit_transaction();
+        arena.replace(b, 12);
+
+        let snapshot =
+            |arena: &JournaledArena<u32>| -> Vec<u32> { arena.iter().map(|(_, &x)| x).collect() };
+        assert_eq!(snapshot(&arena), [2, 12]);
+        assert!(arena.undo());
+        assert_eq!(snapshot(&arena), [2, 11]);
+        assert!(arena.undo());
+        assert_eq!(snapshot(&arena), [0]);
+        assert!(arena.undo());
+        assert!(arena.is_empty());
+        assert!(!arena.undo());
+
+        assert!(arena.redo());
+        assert!(arena.redo());
+        assert_eq!(snapshot(&arena), [2, 11]);
+
+        // A new change discards the rest of the redo history.
+        arena.replace(a, 3);
+        assert!(!arena.can_redo());
+        assert_eq!(snapshot(&arena), [3, 11]);
+    }
+
+    #[test]
+    fn journal_never_reuses_undone_ids() {
+        let mut arena = JournaledArena::<u32>::new();
+        let a = arena.alloc(0);
+        assert!(arena.undo());
+        assert_eq!(arena.try_get(a), Err(ArenaError::Removed { index: 0 }));
+
+        // A new allocation discards the redo history, so `a` stays removed.
+        let b = arena.alloc(1);
+        assert_ne!(a, b);
+        assert!(!arena.can_redo());
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena[b], 1);
+        assert_eq!(arena.len(), 1);
+        assert_eq!(arena.iter().collect::<Vec<_>>(), [(b, &1)]);
+        assert_eq!(arena.iter().rev().collect::<Vec<_>>(), [(b, &1)]);
+    }
+}
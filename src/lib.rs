@@ -24,32 +24,62 @@
 //! ## Homogeneous
 //!
 //! This crate's arenas can only contain objects of a single type `T`. If you
-//! need an arena of objects with heterogeneous types, consider another crate.
+//! need to keep objects of several types in one container, use `AnyArena`,
+//! which holds one arena per type and still returns typed `Id<T>`s.
 //!
 //! ## `#![no_std]` Support
 //!
 //! This crate supports `no_std` environments that have access to the `alloc`
-//! crate. Disable the on-by-default `"std"` feature:
+//! crate. Disable the on-by-default `"std"` feature and enable the `"alloc"`
+//! feature:
 //!
 //! ```toml
 //! [dependencies.id-arena]
-//! version = "2"
+//! version = "3"
 //! default-features = false
+//! features = ["alloc"]
 //! ```
 //!
+//! Without the `"alloc"` feature, the heap-allocated `Arena` is unavailable,
+//! but the fixed-capacity `ArrayArena` still is. It stores its items inline and
+//! returns an error instead of growing when it is full, so the same id-based
+//! code can run on targets without a heap.
+//!
 //! ## `rayon` Support
 //!
 //! If the `rayon` feature of this crate is activated:
 //!
 //! ```toml
 //! [dependencies]
-//! id-arena = { version = "2", features = ["rayon"] }
+//! id-arena = { version = "3", features = ["rayon"] }
 //! ```
 //!
 //! then you can use [`rayon`](https://crates.io/crates/rayon)'s support for
 //! parallel iteration. The `Arena` type will have a `par_iter` family of
 //! methods where appropriate.
 //!
+//! ## `rkyv` Support
+//!
+//! If the `rkyv` feature of this crate is activated, then
+//! `Arena::to_archive_bytes` serializes an arena with
+//! [`rkyv`](https://crates.io/crates/rkyv), and `ArchivedArena` validates the
+//! resulting bytes and accesses the archived objects in place, by id, without
+//! deserializing them. `Id` can be archived too, as an `ArchivedId`, so archived
+//! objects can refer to each other.
+//!
+//! ## `persist` Support
+//!
+//! If the `persist` feature of this crate is activated:
+//!
+//! ```toml
+//! [dependencies]
+//! id-arena = { version = "3", features = ["persist"] }
+//! ```
+//!
+//! then the `DiskArena` type is available. It stores its objects in a file,
+//! encoded with the `Codec` trait, for arenas that do not fit in memory or
+//! that need to outlive the process.
+//!
 //! ## Example
 //!
 //! ```rust
@@ -101,7 +131,7 @@
 #![forbid(unsafe_code)]
 #![deny(missing_debug_implementations)]
 #![deny(missing_docs)]
-// In no-std mode, use the alloc crate to get `Vec`.
+// In no-std mode, use the alloc crate (if enabled) to get `Vec`.
 #![no_std]
 
 use core::cmp::Ordering;
@@ -111,17 +141,142 @@ use core::iter;
 use core::marker::PhantomData;
 use core::ops;
 use core::slice;
-use core::sync::atomic::{self, AtomicUsize};
+use core::sync::atomic;
+#[cfg(target_has_atomic = "32")]
+use core::sync::atomic::AtomicU32 as ArenaCounter;
+#[cfg(not(target_has_atomic = "32"))]
+use core::sync::atomic::AtomicUsize as ArenaCounter;
 
-#[cfg(not(feature = "std"))]
+#[cfg(all(feature = "alloc", not(feature = "std")))]
 extern crate alloc;
-#[cfg(not(feature = "std"))]
-use alloc::vec::{self, Vec};
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    sync::Arc,
+    vec::{self, Vec},
+};
 
 #[cfg(feature = "std")]
 extern crate std;
 #[cfg(feature = "std")]
-use std::vec::{self, Vec};
+use std::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    sync::Arc,
+    vec::{self, Vec},
+};
+
+mod array;
+pub use crate::array::*;
+
+mod error;
+pub use crate::error::*;
+
+#[cfg(feature = "alloc")]
+mod any;
+#[cfg(feature = "alloc")]
+pub use crate::any::*;
+
+#[cfg(feature = "alloc")]
+mod range;
+#[cfg(feature = "alloc")]
+pub use crate::range::*;
+
+#[cfg(feature = "alloc")]
+mod codec;
+#[cfg(feature = "alloc")]
+pub use crate::codec::*;
+
+#[cfg(feature = "alloc")]
+mod diff;
+#[cfg(feature = "alloc")]
+pub use crate::diff::*;
+
+#[cfg(feature = "alloc")]
+mod frozen;
+#[cfg(feature = "alloc")]
+pub use crate::frozen::*;
+
+#[cfg(feature = "alloc")]
+mod identity;
+#[cfg(feature = "alloc")]
+pub use crate::identity::*;
+
+#[cfg(feature = "alloc")]
+mod indexed;
+#[cfg(feature = "alloc")]
+pub use crate::indexed::*;
+
+#[cfg(feature = "alloc")]
+mod journal;
+#[cfg(feature = "alloc")]
+pub use crate::journal::*;
+
+#[cfg(feature = "alloc")]
+mod map;
+#[cfg(feature = "alloc")]
+pub use crate::map::*;
+
+#[cfg(feature = "alloc")]
+mod memo;
+#[cfg(feature = "alloc")]
+pub use crate::memo::*;
+
+#[cfg(feature = "alloc")]
+mod persistent;
+#[cfg(feature = "alloc")]
+pub use crate::persistent::*;
+
+#[cfg(feature = "alloc")]
+mod remap;
+#[cfg(feature = "alloc")]
+pub use crate::remap::*;
+
+#[cfg(feature = "alloc")]
+mod set;
+#[cfg(feature = "alloc")]
+pub use crate::set::*;
+
+#[cfg(feature = "alloc")]
+mod tracked;
+#[cfg(feature = "alloc")]
+pub use crate::tracked::*;
+
+#[cfg(feature = "alloc")]
+mod versioned;
+#[cfg(feature = "alloc")]
+pub use crate::versioned::*;
+
+#[cfg(feature = "alloc")]
+mod world;
+#[cfg(feature = "alloc")]
+pub use crate::world::*;
+
+#[cfg(feature = "alloc")]
+mod branded;
+#[cfg(feature = "alloc")]
+pub use crate::branded::*;
+
+#[cfg(all(feature = "alloc", target_has_atomic = "32"))]
+mod pool;
+#[cfg(all(feature = "alloc", target_has_atomic = "32"))]
+pub use crate::pool::*;
+
+#[cfg(feature = "std")]
+mod names;
+
+#[cfg(feature = "persist")]
+mod disk;
+#[cfg(feature = "persist")]
+pub use crate::disk::*;
+
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "rkyv")]
+pub use crate::archive::*;
 
 #[cfg(feature = "rayon")]
 mod rayon;
@@ -138,18 +293,18 @@ pub use crate::rayon::*;
 /// `ArenaBehavior` yourself:
 ///
 /// * **Space optimizations:** The default identifier is two words in size,
-/// which is larger than is usually necessary. For example, if you know that an
-/// arena *cannot* contain more than 256 items, you could make your own
-/// identifier type that stores the index as a `u8` and then you can save some
-/// space.
+///   which is larger than is usually necessary. For example, if you know that
+///   an arena *cannot* contain more than 256 items, you could make your own
+///   identifier type that stores the index as a `u8` and then you can save
+///   some space.
 ///
 /// * **Trait Coherence:** If you need to implement an upstream crate's traits
-/// for identifiers, then defining your own identifier type allows you to work
-/// with trait coherence rules.
+///   for identifiers, then defining your own identifier type allows you to
+///   work with trait coherence rules.
 ///
 /// * **Share identifiers across arenas:** You can coordinate and share
-/// identifiers across different arenas to enable a "struct of arrays" style
-/// data representation.
+///   identifiers across different arenas to enable a "struct of arrays" style
+///   data representation.
 pub trait ArenaBehavior {
     /// The identifier type.
     type Id: Copy;
@@ -179,13 +334,63 @@ pub trait ArenaBehavior {
     ///
     /// To make identifiers with the same index from different arenas compare
     /// true for equality, return the same `u32` on every invocation.
+    ///
+    /// ## Panics
+    ///
+    /// The provided implementation panics once it has handed out `2^30` arena
+    /// ids, rather than wrapping around and silently reusing the ids of arenas
+    /// that may still be alive. Long-running programs that create very many
+    /// short-lived arenas should create them through an `ArenaIdPool` and
+    /// recycle them, or they will eventually hit this panic.
     fn new_arena_id() -> u32 {
-        static ARENA_COUNTER: AtomicUsize = AtomicUsize::new(0);
-        ARENA_COUNTER.fetch_add(1, atomic::Ordering::SeqCst) as u32
+        static ARENA_COUNTER: ArenaCounter = ArenaCounter::new(0);
+        next_arena_id(&ARENA_COUNTER)
     }
 }
 
+// On targets without 32-bit atomics, `ArenaCounter` is an `AtomicUsize`.
+#[allow(clippy::unnecessary_cast)]
+fn next_arena_id(counter: &ArenaCounter) -> u32 {
+    counter
+        .fetch_update(atomic::Ordering::SeqCst, atomic::Ordering::SeqCst, |id| {
+            id.checked_add(1)
+                .filter(|_| (id as u64) < u64::from(POOLED_ARENA_ID))
+        })
+        .expect("exhausted all arena ids") as u32
+}
+
+/// The bit that is set in arena ids that were chosen explicitly, e.g. with
+/// `Arena::with_arena_id`, or by `DeterministicArenaBehavior`.
+///
+/// The default `ArenaBehavior::new_arena_id` only hands out arena ids without
+/// this bit, so that arenas with explicit arena ids never share an arena id
+/// with other arenas by accident.
+const EXPLICIT_ARENA_ID: u32 = 1 << 31;
+
+/// The bit that is set in the arena ids of arenas from an `ArenaIdPool`.
+///
+/// The default `ArenaBehavior::new_arena_id` only hands out arena ids below
+/// this bit, so that pooled arena ids never collide with them.
+const POOLED_ARENA_ID: u32 = 1 << 30;
+
+/// Map an arena id that was chosen explicitly into the range of explicit arena
+/// ids.
+#[cfg(feature = "alloc")]
+#[track_caller]
+pub(crate) fn explicit_arena_id(arena_id: u32) -> u32 {
+    assert!(
+        arena_id < EXPLICIT_ARENA_ID,
+        "explicit arena id {} is out of range: it must be less than 2^31",
+        arena_id
+    );
+    arena_id | EXPLICIT_ARENA_ID
+}
+
 /// An identifier for an object allocated within an arena.
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Id<T> {
     idx: usize,
     arena_id: u32,
@@ -194,7 +399,39 @@ pub struct Id<T> {
 
 impl<T> fmt::Debug for Id<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Id").field("idx", &self.idx).finish()
+        #[cfg(feature = "std")]
+        {
+            if let Some(name) = names::arena_name(self.arena_id) {
+                return write!(f, "{}#{}", name, self.idx);
+            }
+        }
+        let mut debug = f.debug_struct("Id");
+        debug
+            .field("idx", &self.idx)
+            .field("arena_id", &(self.arena_id & !EXPLICIT_ARENA_ID));
+        if self.arena_id & EXPLICIT_ARENA_ID != 0 {
+            debug.field("explicit", &true);
+        }
+        debug.finish()
+    }
+}
+
+/// Formats an arena id for humans: as the arena's name, if it has one.
+struct ArenaLabel(u32);
+
+impl fmt::Display for ArenaLabel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "std")]
+        {
+            if let Some(name) = names::arena_name(self.0) {
+                return f.write_str(&name);
+            }
+        }
+        if self.0 & EXPLICIT_ARENA_ID != 0 {
+            write!(f, "explicit-arena{}", self.0 & !EXPLICIT_ARENA_ID)
+        } else {
+            write!(f, "arena{}", self.0)
+        }
     }
 }
 
@@ -244,6 +481,21 @@ impl<T> Id<T> {
     pub fn index(&self) -> usize {
         self.idx
     }
+
+    /// Convert this id into an id of a `U` with the same arena id and index.
+    ///
+    /// This is useful for ids inside items being transformed by `Arena::map`,
+    /// whose resulting arena's ids correspond index-for-index to the original
+    /// arena's ids. Use `Arena::convert_id` to convert ids that may not belong
+    /// to the arena.
+    #[inline]
+    pub fn cast<U>(self) -> Id<U> {
+        Id {
+            idx: self.idx,
+            arena_id: self.arena_id,
+            _ty: PhantomData,
+        }
+    }
 }
 
 /// The default `ArenaBehavior` implementation.
@@ -275,6 +527,103 @@ impl<T> ArenaBehavior for DefaultArenaBehavior<T> {
     }
 }
 
+/// An `ArenaBehavior` whose arena ids are deterministic.
+///
+/// Arena ids are handed out by a counter that is local to the current thread,
+/// so that a given thread always assigns the same arena ids in the same order,
+/// no matter how many arenas other threads have created. This makes the
+/// ordering and hashing of `Id`s reproducible, e.g. in snapshot tests that run
+/// in parallel.
+///
+/// Arenas created on different threads may have the same arena id, so their
+/// ids are not disambiguated. Use `Arena::with_arena_id` instead when arenas
+/// need to cross threads.
+///
+/// Deterministic arena ids share the range of explicit arena ids with
+/// `Arena::with_arena_id`, which is separate from the arena ids handed out by
+/// `DefaultArenaBehavior`, so an id from a deterministic arena is never
+/// mistaken for an id from an arena with a default arena id.
+///
+/// ```
+/// use id_arena::DeterministicArenaBehavior;
+///
+/// type Arena<T> = id_arena::Arena<T, DeterministicArenaBehavior<T>>;
+///
+/// let mut a = Arena::<u32>::new();
+/// let mut b = Arena::<u32>::new();
+/// let a0 = a.alloc(0);
+/// assert!(a0 < b.alloc(0));
+///
+/// // Starting over assigns the same arena ids again.
+/// DeterministicArenaBehavior::<()>::reset();
+/// let mut c = Arena::<u32>::new();
+/// assert_eq!(c.alloc(0), a0);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeterministicArenaBehavior<T> {
+    _phantom: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static DETERMINISTIC_ARENA_COUNTER: core::cell::Cell<u32> = const {
+        core::cell::Cell::new(0)
+    };
+}
+
+#[cfg(feature = "std")]
+impl<T> DeterministicArenaBehavior<T> {
+    /// Restart the current thread's arena id counter from zero.
+    ///
+    /// The counter is shared by all `DeterministicArenaBehavior<T>` types,
+    /// whatever their `T`.
+    ///
+    /// Arenas created after a reset get the same arena ids as the arenas
+    /// created before it. If any of those are still alive, their ids collide
+    /// with the ids of the new arenas, so only reset the counter once every
+    /// deterministic arena of the current thread has been dropped. The names
+    /// of the current thread's explicit and deterministic arenas are
+    /// forgotten; other threads are unaffected.
+    pub fn reset() {
+        names::remove_explicit_arena_names();
+        DETERMINISTIC_ARENA_COUNTER.with(|counter| counter.set(0));
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ArenaBehavior for DeterministicArenaBehavior<T> {
+    type Id = Id<T>;
+
+    #[inline]
+    fn new_id(arena_id: u32, idx: usize) -> Self::Id {
+        Id {
+            idx,
+            arena_id,
+            _ty: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn index(id: Self::Id) -> usize {
+        id.idx
+    }
+
+    #[inline]
+    fn arena_id(id: Self::Id) -> u32 {
+        id.arena_id
+    }
+
+    fn new_arena_id() -> u32 {
+        DETERMINISTIC_ARENA_COUNTER.with(|counter| {
+            let id = counter.get();
+            assert!(id < EXPLICIT_ARENA_ID, "exhausted all arena ids");
+            counter.set(id + 1);
+            id | EXPLICIT_ARENA_ID
+        })
+    }
+}
+
 /// An arena of objects of type `T`.
 ///
 /// ```
@@ -288,6 +637,7 @@ impl<T> ArenaBehavior for DefaultArenaBehavior<T> {
 /// arena[a] = "Alice";
 /// assert_eq!(arena[a], "Alice");
 /// ```
+#[cfg(feature = "alloc")]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Arena<T, A = DefaultArenaBehavior<T>> {
     arena_id: u32,
@@ -295,6 +645,7 @@ pub struct Arena<T, A = DefaultArenaBehavior<T>> {
     _phantom: PhantomData<fn() -> A>,
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> Default for Arena<T, A>
 where
     A: ArenaBehavior,
@@ -309,6 +660,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> Arena<T, A>
 where
     A: ArenaBehavior,
@@ -346,6 +698,54 @@ where
         }
     }
 
+    /// Construct a new, empty `Arena` with the given arena id, rather than one
+    /// created by `A::new_arena_id()`.
+    ///
+    /// Ids from this arena are equal to, and order and hash the same as, ids
+    /// with the same indices from any other arena with this arena id. Use this
+    /// to make ids reproducible across runs, and make sure that arenas whose
+    /// ids could be mixed up have distinct arena ids.
+    ///
+    /// Explicit arena ids are kept apart from the arena ids that
+    /// `DefaultArenaBehavior` hands out, so ids from this arena are never
+    /// accepted by an arena that was created with `Arena::new`. To keep them
+    /// apart, the arena id is stored with its high bit set, which is what
+    /// `ArenaBehavior::arena_id` returns for the arena's ids. `Debug` prints
+    /// the arena id as it was given, marked as `explicit`.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut types = Arena::<&str>::with_arena_id(1);
+    /// let mut values = Arena::<&str>::with_arena_id(2);
+    ///
+    /// let ty = types.alloc("i32");
+    /// let value = values.alloc("42");
+    /// assert!(ty < value);
+    /// assert!(values.get(ty).is_none());
+    ///
+    /// let same = Arena::<&str>::with_arena_id(1);
+    /// assert_eq!(same.next_id(), ty);
+    ///
+    /// assert_eq!(format!("{:?}", ty), "Id { idx: 0, arena_id: 1, explicit: true }");
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `arena_id` is not less than `2^31`.
+    #[inline]
+    #[track_caller]
+    pub fn with_arena_id(arena_id: u32) -> Arena<T, A> {
+        let arena_id = explicit_arena_id(arena_id);
+        #[cfg(feature = "std")]
+        names::remove_arena_name(arena_id);
+        Arena {
+            arena_id,
+            items: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
     /// Allocate `item` within this arena and return its id.
     ///
     /// ```
@@ -390,6 +790,26 @@ where
         self.alloc(val)
     }
 
+    /// Allocate every item from `items` within this arena, in order, and return
+    /// the range of their ids.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<char>::new();
+    /// arena.alloc('a');
+    ///
+    /// let ids = arena.alloc_extend("bcd".chars());
+    /// assert_eq!(ids.len(), 3);
+    /// assert_eq!(ids.map(|id| arena[id]).collect::<String>(), "bcd");
+    /// ```
+    #[inline]
+    pub fn alloc_extend(&mut self, items: impl IntoIterator<Item = T>) -> IdRange<T, A> {
+        let start = self.items.len();
+        self.items.extend(items);
+        IdRange::new(self.arena_id, start, self.items.len())
+    }
+
     /// Get the id that will be used for the next item allocated into this
     /// arena.
     ///
@@ -454,6 +874,108 @@ where
         }
     }
 
+    /// Get a shared reference to the object associated with the given `id`, or
+    /// an error describing why there is none.
+    ///
+    /// ```
+    /// use id_arena::{Arena, ArenaError};
+    ///
+    /// let mut arena = Arena::<usize>::new();
+    /// let id = arena.alloc(42);
+    /// assert_eq!(arena.try_get(id), Ok(&42));
+    ///
+    /// let other_arena = Arena::<usize>::new();
+    /// assert!(matches!(
+    ///     other_arena.try_get(id),
+    ///     Err(ArenaError::WrongArena { .. })
+    /// ));
+    /// ```
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&T, ArenaError> {
+        let index = check_id(self.arena_id, self.len(), A::arena_id(id), A::index(id))?;
+        Ok(&self.items[index])
+    }
+
+    /// Get an exclusive reference to the object associated with the given
+    /// `id`, or an error describing why there is none.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<usize>::new();
+    /// let id = arena.alloc(42);
+    /// *arena.try_get_mut(id).unwrap() += 1;
+    /// assert_eq!(arena[id], 43);
+    /// ```
+    #[inline]
+    pub fn try_get_mut(&mut self, id: A::Id) -> Result<&mut T, ArenaError> {
+        let index = check_id(self.arena_id, self.len(), A::arena_id(id), A::index(id))?;
+        Ok(&mut self.items[index])
+    }
+
+    /// Transform every item in this arena, producing a new arena whose ids
+    /// correspond index-for-index to this arena's ids.
+    ///
+    /// The new arena keeps this arena's arena id, so that ids from this arena
+    /// can be converted into ids of the new arena with `Arena::convert_id`.
+    ///
+    /// ```
+    /// use id_arena::{Arena, DefaultArenaBehavior, Id};
+    ///
+    /// enum Expr {
+    ///     Const(i64),
+    ///     Neg(Id<Expr>),
+    /// }
+    ///
+    /// enum Inst {
+    ///     Load(i64),
+    ///     Neg(Id<Inst>),
+    /// }
+    ///
+    /// let mut exprs = Arena::<Expr>::new();
+    /// let one = exprs.alloc(Expr::Const(1));
+    /// let neg_one = exprs.alloc(Expr::Neg(one));
+    ///
+    /// let insts: Arena<Inst> = exprs.map(|_, expr| match expr {
+    ///     Expr::Const(c) => Inst::Load(c),
+    ///     // Ids inside the items correspond index-for-index too.
+    ///     Expr::Neg(e) => Inst::Neg(e.cast()),
+    /// });
+    ///
+    /// let neg_one = insts
+    ///     .convert_id::<DefaultArenaBehavior<Expr>>(neg_one)
+    ///     .unwrap();
+    /// match insts[neg_one] {
+    ///     Inst::Neg(one) => assert!(matches!(insts[one], Inst::Load(1))),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn map<U, B>(self, mut f: impl FnMut(A::Id, T) -> U) -> Arena<U, B>
+    where
+        B: ArenaBehavior,
+    {
+        Arena {
+            arena_id: self.arena_id,
+            items: self.into_iter().map(|(id, item)| f(id, item)).collect(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Convert an id from the arena that this arena was created from with
+    /// `Arena::map` into the id of the corresponding item in this arena.
+    ///
+    /// Returns `None` if `id` does not correspond to an item in this arena.
+    ///
+    /// See [the `Arena::map()` method](#method.map) for an example.
+    #[inline]
+    pub fn convert_id<B>(&self, id: B::Id) -> Option<A::Id>
+    where
+        B: ArenaBehavior,
+    {
+        let index = check_id(self.arena_id, self.len(), B::arena_id(id), B::index(id)).ok()?;
+        Some(A::new_id(self.arena_id, index))
+    }
+
     /// Iterate over this arena's items and their ids.
     ///
     /// ```
@@ -471,14 +993,14 @@ where
     /// }
     /// ```
     #[inline]
-    pub fn iter(&self) -> Iter<T, A> {
+    pub fn iter(&self) -> Iter<'_, T, A> {
         IntoIterator::into_iter(self)
     }
 
     /// Iterate over this arena's items and their ids, allowing mutation of each
     /// item.
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<T, A> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, A> {
         IntoIterator::into_iter(self)
     }
 
@@ -498,8 +1020,25 @@ where
     pub fn len(&self) -> usize {
         self.items.len()
     }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<&str>::new();
+    /// assert!(arena.is_empty());
+    ///
+    /// arena.alloc("hello");
+    /// assert!(!arena.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> ops::Index<A::Id> for Arena<T, A>
 where
     A: ArenaBehavior,
@@ -507,23 +1046,31 @@ where
     type Output = T;
 
     #[inline]
+    #[track_caller]
     fn index(&self, id: A::Id) -> &T {
-        assert_eq!(self.arena_id, A::arena_id(id));
-        &self.items[A::index(id)]
+        match self.try_get(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> ops::IndexMut<A::Id> for Arena<T, A>
 where
     A: ArenaBehavior,
 {
     #[inline]
+    #[track_caller]
     fn index_mut(&mut self, id: A::Id) -> &mut T {
-        assert_eq!(self.arena_id, A::arena_id(id));
-        &mut self.items[A::index(id)]
+        match self.try_get_mut(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
     }
 }
 
+#[cfg(feature = "alloc")]
 fn add_id<A, T>(item: Option<(usize, T)>, arena_id: u32) -> Option<(A::Id, T)>
 where
     A: ArenaBehavior,
@@ -531,9 +1078,36 @@ where
     item.map(|(idx, item)| (A::new_id(arena_id, idx), item))
 }
 
+#[cfg(feature = "alloc")]
+impl<T, A> Extend<T> for Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        self.items.extend(items);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, A> iter::FromIterator<T> for Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Arena<T, A> {
+        Arena {
+            arena_id: A::new_arena_id(),
+            items: Vec::from_iter(items),
+            _phantom: PhantomData,
+        }
+    }
+}
+
 /// An iterator over `(Id, &T)` pairs in an arena.
 ///
 /// See [the `Arena::iter()` method](./struct.Arena.html#method.iter) for details.
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub struct Iter<'a, T: 'a, A: 'a> {
     arena_id: u32,
@@ -541,6 +1115,7 @@ pub struct Iter<'a, T: 'a, A: 'a> {
     _phantom: PhantomData<fn() -> A>,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: 'a, A: 'a> Iterator for Iter<'a, T, A>
 where
     A: ArenaBehavior,
@@ -557,6 +1132,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: 'a, A: 'a> DoubleEndedIterator for Iter<'a, T, A>
 where
     A: ArenaBehavior,
@@ -566,6 +1142,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: 'a, A: 'a> ExactSizeIterator for Iter<'a, T, A>
 where
     A: ArenaBehavior,
@@ -575,6 +1152,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T, A> IntoIterator for &'a Arena<T, A>
 where
     A: ArenaBehavior,
@@ -596,6 +1174,7 @@ where
 ///
 /// See [the `Arena::iter_mut()` method](./struct.Arena.html#method.iter_mut)
 /// for details.
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub struct IterMut<'a, T: 'a, A: 'a> {
     arena_id: u32,
@@ -603,6 +1182,7 @@ pub struct IterMut<'a, T: 'a, A: 'a> {
     _phantom: PhantomData<fn() -> A>,
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: 'a, A: 'a> Iterator for IterMut<'a, T, A>
 where
     A: ArenaBehavior,
@@ -619,6 +1199,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: 'a, A: 'a> DoubleEndedIterator for IterMut<'a, T, A>
 where
     A: ArenaBehavior,
@@ -628,6 +1209,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T: 'a, A: 'a> ExactSizeIterator for IterMut<'a, T, A>
 where
     A: ArenaBehavior,
@@ -637,6 +1219,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'a, T, A> IntoIterator for &'a mut Arena<T, A>
 where
     A: ArenaBehavior,
@@ -655,6 +1238,7 @@ where
 }
 
 /// An iterator over `(Id, T)` pairs in an arena.
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub struct IntoIter<T, A> {
     arena_id: u32,
@@ -662,6 +1246,7 @@ pub struct IntoIter<T, A> {
     _phantom: PhantomData<fn() -> A>,
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> Iterator for IntoIter<T, A>
 where
     A: ArenaBehavior,
@@ -678,6 +1263,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> DoubleEndedIterator for IntoIter<T, A>
 where
     A: ArenaBehavior,
@@ -687,6 +1273,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> ExactSizeIterator for IntoIter<T, A>
 where
     A: ArenaBehavior,
@@ -696,6 +1283,7 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<T, A> IntoIterator for Arena<T, A>
 where
     A: ArenaBehavior,
@@ -723,4 +1311,47 @@ mod tests {
         struct Foo;
         assert_send_sync::<Id<Foo>>();
     }
+
+    #[test]
+    fn arena_ids_are_unique_until_exhausted() {
+        let counter = ArenaCounter::new((POOLED_ARENA_ID - 2) as _);
+        assert_eq!(next_arena_id(&counter), POOLED_ARENA_ID - 2);
+        assert_eq!(next_arena_id(&counter), POOLED_ARENA_ID - 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn explicit_ids_are_rejected_by_default_arenas() {
+        let mut default = Arena::<u32>::new();
+        default.alloc(222);
+
+        let mut deterministic = Arena::<u32, DeterministicArenaBehavior<u32>>::new();
+        let id = deterministic.alloc(0);
+        assert_eq!(default.get(id), None);
+
+        let mut explicit = Arena::<u32>::with_arena_id(0);
+        let id = explicit.alloc(0);
+        assert_eq!(default.get(id), None);
+        assert_eq!(
+            std::format!("{}", default.try_get(id).unwrap_err()),
+            std::format!(
+                "id explicit-arena0#0 belongs to explicit-arena0, not arena{}",
+                default.arena_id
+            )
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    #[should_panic(expected = "explicit arena id 2147483648 is out of range")]
+    fn explicit_arena_ids_must_be_in_range() {
+        Arena::<u32>::with_arena_id(1 << 31);
+    }
+
+    #[test]
+    #[should_panic(expected = "exhausted all arena ids")]
+    fn exhausting_arena_ids_panics() {
+        let counter = ArenaCounter::new(POOLED_ARENA_ID as _);
+        next_arena_id(&counter);
+    }
 }
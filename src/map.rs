@@ -0,0 +1,354 @@
+use super::*;
+
+/// A map from the ids of one arena to values, stored densely by index.
+///
+/// This is a cheaper replacement for a `HashMap` or `BTreeMap` keyed by ids
+/// when many of an arena's ids have a value. Like `IdSet`, the map learns
+/// which arena it belongs to from the first id that is inserted into it, and
+/// inserting an id from any other arena panics.
+///
+/// ```
+/// use id_arena::{Arena, ArenaMap};
+///
+/// let mut arena = Arena::<&str>::new();
+/// let a = arena.alloc("a");
+/// let b = arena.alloc("b");
+/// let c = arena.alloc("c");
+///
+/// let mut lengths = ArenaMap::<&str, usize>::new();
+/// lengths.insert(c, 3);
+/// lengths.insert(a, 1);
+///
+/// assert_eq!(lengths.get(a), Some(&1));
+/// assert_eq!(lengths.get(b), None);
+/// assert_eq!(lengths.iter().collect::<Vec<_>>(), [(a, &1), (c, &3)]);
+/// ```
+pub struct ArenaMap<T, V, A = DefaultArenaBehavior<T>> {
+    arena_id: Option<u32>,
+    values: Vec<Option<V>>,
+    len: usize,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, V, A> fmt::Debug for ArenaMap<T, V, A>
+where
+    A: ArenaBehavior,
+    A::Id: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T, V, A> Clone for ArenaMap<T, V, A>
+where
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        ArenaMap {
+            arena_id: self.arena_id,
+            values: self.values.clone(),
+            len: self.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, V, A> Default for ArenaMap<T, V, A> {
+    #[inline]
+    fn default() -> Self {
+        ArenaMap {
+            arena_id: None,
+            values: Vec::new(),
+            len: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, V, A> ArenaMap<T, V, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `ArenaMap`.
+    #[inline]
+    pub fn new() -> ArenaMap<T, V, A> {
+        Default::default()
+    }
+
+    #[inline]
+    fn index(&self, id: A::Id) -> Option<usize> {
+        if self.arena_id == Some(A::arena_id(id)) {
+            Some(A::index(id))
+        } else {
+            None
+        }
+    }
+
+    /// Insert `value` for `id`, and return the previous value for `id`, if
+    /// any.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `id` is from a different arena than the ids already inserted
+    /// into this map.
+    #[track_caller]
+    pub fn insert(&mut self, id: A::Id, value: V) -> Option<V> {
+        let arena_id = *self.arena_id.get_or_insert(A::arena_id(id));
+        if arena_id != A::arena_id(id) {
+            panic!(
+                "cannot insert into `ArenaMap`: {}",
+                ArenaError::WrongArena {
+                    expected: arena_id,
+                    found: A::arena_id(id),
+                    index: Some(A::index(id)),
+                }
+            );
+        }
+        let index = A::index(id);
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        let old = self.values[index].replace(value);
+        self.len += old.is_none() as usize;
+        old
+    }
+
+    /// Remove the value for `id` from this map, and return it if it was
+    /// present.
+    pub fn remove(&mut self, id: A::Id) -> Option<V> {
+        let index = self.index(id)?;
+        let old = self.values.get_mut(index)?.take();
+        self.len -= old.is_some() as usize;
+        old
+    }
+
+    /// Get a shared reference to the value for `id`, if any.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&V> {
+        self.values.get(self.index(id)?)?.as_ref()
+    }
+
+    /// Get an exclusive reference to the value for `id`, if any.
+    #[inline]
+    pub fn get_mut(&mut self, id: A::Id) -> Option<&mut V> {
+        let index = self.index(id)?;
+        self.values.get_mut(index)?.as_mut()
+    }
+
+    /// Returns `true` if this map has a value for `id`.
+    #[inline]
+    pub fn contains_key(&self, id: A::Id) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Iterate over the ids in this map and their values, in order of their
+    /// indices.
+    #[inline]
+    pub fn iter(&self) -> ArenaMapIter<'_, T, V, A> {
+        ArenaMapIter {
+            arena_id: self.arena_id.unwrap_or(0),
+            iter: self.values.iter().enumerate(),
+            len: self.len,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Iterate over the ids in this map and exclusive references to their
+    /// values, in order of their indices.
+    #[inline]
+    pub fn iter_mut(&mut self) -> ArenaMapIterMut<'_, T, V, A> {
+        ArenaMapIterMut {
+            arena_id: self.arena_id.unwrap_or(0),
+            iter: self.values.iter_mut().enumerate(),
+            len: self.len,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Remove every value from this map.
+    ///
+    /// The map stays tied to the arena it was used with.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.len = 0;
+    }
+
+    /// Get the number of ids in this map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if this map has no values.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T, V, A> ops::Index<A::Id> for ArenaMap<T, V, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = V;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &V {
+        self.get(id).expect("no value for id in `ArenaMap`")
+    }
+}
+
+impl<T, V, A> ops::IndexMut<A::Id> for ArenaMap<T, V, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, id: A::Id) -> &mut V {
+        self.get_mut(id).expect("no value for id in `ArenaMap`")
+    }
+}
+
+/// An iterator over `(Id, &V)` pairs in an `ArenaMap`, in order of the ids'
+/// indices.
+///
+/// See [the `ArenaMap::iter()`
+/// method](./struct.ArenaMap.html#method.iter) for details.
+#[derive(Debug)]
+pub struct ArenaMapIter<'a, T, V: 'a, A> {
+    arena_id: u32,
+    iter: iter::Enumerate<slice::Iter<'a, Option<V>>>,
+    len: usize,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<'a, T, V: 'a, A> Iterator for ArenaMapIter<'a, T, V, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, value) = self
+            .iter
+            .find_map(|(index, value)| Some((index, value.as_ref()?)))?;
+        self.len -= 1;
+        Some((A::new_id(self.arena_id, index), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, V: 'a, A> DoubleEndedIterator for ArenaMapIter<'a, T, V, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (index, value) = self
+            .iter
+            .by_ref()
+            .rev()
+            .find_map(|(index, value)| Some((index, value.as_ref()?)))?;
+        self.len -= 1;
+        Some((A::new_id(self.arena_id, index), value))
+    }
+}
+
+impl<'a, T, V: 'a, A> ExactSizeIterator for ArenaMapIter<'a, T, V, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// An iterator over `(Id, &mut V)` pairs in an `ArenaMap`, in order of the ids'
+/// indices.
+///
+/// See [the `ArenaMap::iter_mut()`
+/// method](./struct.ArenaMap.html#method.iter_mut) for details.
+#[derive(Debug)]
+pub struct ArenaMapIterMut<'a, T, V: 'a, A> {
+    arena_id: u32,
+    iter: iter::Enumerate<slice::IterMut<'a, Option<V>>>,
+    len: usize,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<'a, T, V: 'a, A> Iterator for ArenaMapIterMut<'a, T, V, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, value) = self
+            .iter
+            .find_map(|(index, value)| Some((index, value.as_mut()?)))?;
+        self.len -= 1;
+        Some((A::new_id(self.arena_id, index), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, V: 'a, A> DoubleEndedIterator for ArenaMapIterMut<'a, T, V, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (index, value) = self
+            .iter
+            .by_ref()
+            .rev()
+            .find_map(|(index, value)| Some((index, value.as_mut()?)))?;
+        self.len -= 1;
+        Some((A::new_id(self.arena_id, index), value))
+    }
+}
+
+impl<'a, T, V: 'a, A> ExactSizeIterator for ArenaMapIterMut<'a, T, V, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T, V, A> IntoIterator for &'a ArenaMap<T, V, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a V);
+    type IntoIter = ArenaMapIter<'a, T, V, A>;
+
+    #[inline]
+    fn into_iter(self) -> ArenaMapIter<'a, T, V, A> {
+        self.iter()
+    }
+}
+
+impl<'a, T, V, A> IntoIterator for &'a mut ArenaMap<T, V, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a mut V);
+    type IntoIter = ArenaMapIterMut<'a, T, V, A>;
+
+    #[inline]
+    fn into_iter(self) -> ArenaMapIterMut<'a, T, V, A> {
+        self.iter_mut()
+    }
+}
@@ -0,0 +1,285 @@
+use super::*;
+
+/// A table of lazily computed values, one per id of an arena.
+///
+/// Values are stored densely by index, and computed by the function passed to
+/// `get_or_compute` the first time an id is accessed. The function gets the
+/// memo table itself, so that it can ask for the values of other ids. Asking
+/// for the value of an id that is still being computed is a cycle, and returns
+/// a `CycleError` instead of recursing forever.
+///
+/// Like `IdSet`, the table learns which arena it belongs to from the first id
+/// that is used with it, and using an id from any other arena panics.
+///
+/// ```
+/// use id_arena::{Arena, CycleError, Id, Memo};
+///
+/// struct Node {
+///     parent: Option<Id<Node>>,
+/// }
+///
+/// let mut nodes = Arena::<Node>::new();
+/// let root = nodes.alloc(Node { parent: None });
+/// let child = nodes.alloc(Node { parent: Some(root) });
+/// let grandchild = nodes.alloc(Node { parent: Some(child) });
+///
+/// let mut depths = Memo::<Node, usize>::new();
+/// fn depth(nodes: &Arena<Node>, depths: &mut Memo<Node, usize>, id: Id<Node>) -> usize {
+///     *depths
+///         .get_or_compute(id, |depths, id| match nodes[id].parent {
+///             Some(parent) => depth(nodes, depths, parent) + 1,
+///             None => 0,
+///         })
+///         .unwrap()
+/// }
+///
+/// assert_eq!(depth(&nodes, &mut depths, grandchild), 2);
+/// // The depths of the ancestors were computed along the way.
+/// assert_eq!(depths.get(child), Some(&1));
+///
+/// // A cycle is reported instead of overflowing the stack.
+/// let mut cyclic = Memo::<Node, Result<usize, CycleError>>::new();
+/// let result = cyclic.get_or_compute(root, |cyclic, id| {
+///     let value = cyclic.get_or_compute(id, |_, _| Ok(0))?;
+///     value.clone()
+/// });
+/// assert_eq!(result.unwrap().unwrap_err().index(), 0);
+/// ```
+pub struct Memo<T, V, A = DefaultArenaBehavior<T>> {
+    arena_id: Option<u32>,
+    slots: Vec<Slot<V>>,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+#[derive(Clone, Debug)]
+enum Slot<V> {
+    Vacant,
+    InProgress,
+    Done(V),
+}
+
+impl<T, V, A> fmt::Debug for Memo<T, V, A>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Memo")
+            .field("arena_id", &self.arena_id)
+            .field("slots", &self.slots)
+            .finish()
+    }
+}
+
+impl<T, V, A> Clone for Memo<T, V, A>
+where
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Memo {
+            arena_id: self.arena_id,
+            slots: self.slots.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, V, A> Default for Memo<T, V, A> {
+    #[inline]
+    fn default() -> Self {
+        Memo {
+            arena_id: None,
+            slots: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, V, A> Memo<T, V, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `Memo`.
+    #[inline]
+    pub fn new() -> Memo<T, V, A> {
+        Default::default()
+    }
+
+    #[track_caller]
+    fn check_arena(&mut self, id: A::Id) -> usize {
+        let arena_id = *self.arena_id.get_or_insert(A::arena_id(id));
+        if arena_id != A::arena_id(id) {
+            panic!(
+                "cannot use id with `Memo`: {}",
+                ArenaError::WrongArena {
+                    expected: arena_id,
+                    found: A::arena_id(id),
+                    index: Some(A::index(id)),
+                }
+            );
+        }
+        A::index(id)
+    }
+
+    /// Get the value for `id`, computing it with `compute` if it has not been
+    /// computed yet.
+    ///
+    /// `compute` is given this memo table and `id`, and may get the values of
+    /// other ids from the table.
+    ///
+    /// Returns a `CycleError` if the value for `id` is already being computed
+    /// further up the stack. If `compute` panics, the value for `id` is left
+    /// in progress until it is invalidated.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `id` is from a different arena than the ids already used with
+    /// this table.
+    #[track_caller]
+    pub fn get_or_compute<F>(&mut self, id: A::Id, compute: F) -> Result<&V, CycleError>
+    where
+        F: FnOnce(&mut Self, A::Id) -> V,
+    {
+        let index = self.check_arena(id);
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || Slot::Vacant);
+        }
+        match self.slots[index] {
+            Slot::Done(_) => {}
+            Slot::InProgress => {
+                return Err(CycleError {
+                    arena_id: A::arena_id(id),
+                    index,
+                })
+            }
+            Slot::Vacant => {
+                self.slots[index] = Slot::InProgress;
+                let value = compute(self, id);
+                // `compute` may have cleared the table.
+                if index >= self.slots.len() {
+                    self.slots.resize_with(index + 1, || Slot::Vacant);
+                }
+                self.slots[index] = Slot::Done(value);
+            }
+        }
+        match &self.slots[index] {
+            Slot::Done(value) => Ok(value),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Get the value for `id` if it has been computed.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&V> {
+        if self.arena_id != Some(A::arena_id(id)) {
+            return None;
+        }
+        match self.slots.get(A::index(id)) {
+            Some(Slot::Done(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Forget the value for `id`, so that it is computed again by the next
+    /// call to `get_or_compute`, and return it if it had been computed.
+    pub fn invalidate(&mut self, id: A::Id) -> Option<V> {
+        if self.arena_id != Some(A::arena_id(id)) {
+            return None;
+        }
+        match self.slots.get_mut(A::index(id)) {
+            Some(slot) => match core::mem::replace(slot, Slot::Vacant) {
+                Slot::Done(value) => Some(value),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Forget every value in this table.
+    ///
+    /// The table stays tied to the arena it was used with.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+}
+
+/// The error returned from
+/// [`Memo::get_or_compute`](./struct.Memo.html#method.get_or_compute) when a
+/// value depends on itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CycleError {
+    arena_id: u32,
+    index: usize,
+}
+
+impl CycleError {
+    /// Get the arena id of the id whose value depends on itself.
+    #[inline]
+    pub fn arena_id(&self) -> u32 {
+        self.arena_id
+    }
+
+    /// Get the index of the id whose value depends on itself.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cycle detected: the value for {}#{} depends on itself",
+            ArenaLabel(self.arena_id),
+            self.index
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CycleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memo_invalidation_recomputes() {
+        let mut arena = Arena::<u32>::new();
+        let a = arena.alloc(1);
+        let b = arena.alloc(2);
+        let mut memo = Memo::<u32, u32>::new();
+        let calls = core::cell::Cell::new(0);
+        let double = |arena: &Arena<u32>, memo: &mut Memo<u32, u32>, id| {
+            *memo
+                .get_or_compute(id, |_, id| {
+                    calls.set(calls.get() + 1);
+                    arena[id] * 2
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            (double(&arena, &mut memo, a), double(&arena, &mut memo, b)),
+            (2, 4)
+        );
+        assert_eq!(double(&arena, &mut memo, a), 2);
+
+        arena[a] = 10;
+        assert_eq!(memo.invalidate(a), Some(2));
+        assert_eq!(memo.invalidate(a), None);
+        assert_eq!(double(&arena, &mut memo, a), 20);
+        memo.clear();
+        assert_eq!(memo.get(b), None);
+        assert_eq!(double(&arena, &mut memo, b), 4);
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot use id with `Memo`")]
+    fn memo_rejects_ids_from_other_arenas() {
+        let mut memo = Memo::<u32, u32>::new();
+        let _ = memo.get_or_compute(Arena::<u32>::new().next_id(), |_, _| 0);
+        let _ = memo.get_or_compute(Arena::<u32>::new().next_id(), |_, _| 0);
+    }
+}
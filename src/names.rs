@@ -0,0 +1,141 @@
+use super::*;
+
+use core::cell::RefCell;
+use std::collections::BTreeMap;
+use std::string::String;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Mutex, PoisonError};
+
+/// The names of named arenas with default arena ids, keyed by arena id.
+///
+/// Names outlive their arena, since its ids can, and are only removed when
+/// their arena id is reused.
+static ARENA_NAMES: Mutex<Option<BTreeMap<u32, String>>> = Mutex::new(None);
+
+/// Whether any arena with a default arena id was ever named, so that
+/// formatting ids can skip locking `ARENA_NAMES` in programs that never name
+/// an arena.
+static ANY_ARENA_NAMES: AtomicBool = AtomicBool::new(false);
+
+std::thread_local! {
+    /// The names of named arenas with explicit arena ids, such as those of
+    /// `DeterministicArenaBehavior`, which every thread hands out the same of.
+    static EXPLICIT_ARENA_NAMES: RefCell<BTreeMap<u32, String>> =
+        RefCell::new(BTreeMap::new());
+}
+
+/// Call `f` with the names that the name of `arena_id` would be registered
+/// in, or return `None` if it cannot have a name.
+fn with_arena_names<R>(
+    arena_id: u32,
+    f: impl FnOnce(&mut BTreeMap<u32, String>) -> R,
+) -> Option<R> {
+    if arena_id & EXPLICIT_ARENA_ID != 0 {
+        return Some(EXPLICIT_ARENA_NAMES.with(|names| f(&mut names.borrow_mut())));
+    }
+    if !ANY_ARENA_NAMES.load(atomic::Ordering::Acquire) {
+        return None;
+    }
+    let mut names = ARENA_NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    Some(f(names.get_or_insert_with(BTreeMap::new)))
+}
+
+/// Get the name registered for the given arena id, if any.
+pub(crate) fn arena_name(arena_id: u32) -> Option<String> {
+    with_arena_names(arena_id, |names| names.get(&arena_id).cloned())?
+}
+
+fn set_arena_name(arena_id: u32, name: String) {
+    ANY_ARENA_NAMES.store(true, atomic::Ordering::Release);
+    with_arena_names(arena_id, |names| names.insert(arena_id, name));
+}
+
+/// Forget the name of the given arena id, because it is being reused.
+pub(crate) fn remove_arena_name(arena_id: u32) {
+    with_arena_names(arena_id, |names| names.remove(&arena_id));
+}
+
+/// Forget the names of the current thread's explicit arena ids, because they
+/// are being reused.
+pub(crate) fn remove_explicit_arena_names() {
+    EXPLICIT_ARENA_NAMES.with(|names| names.borrow_mut().clear());
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `Arena` with the given name.
+    ///
+    /// The name is registered for the arena's arena id, and used when
+    /// formatting the arena's ids and panic messages, e.g. `types#42` for the
+    /// object at index 42 of the arena named `types`. Names stay registered
+    /// until the arena id is reused, e.g. by `Arena::with_arena_id`, so avoid
+    /// naming a large number of arenas. Arenas with explicit arena ids, such
+    /// as those of `DeterministicArenaBehavior`, are only named on the current
+    /// thread.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut types = Arena::<&str>::with_name("types");
+    /// let id = types.alloc("i32");
+    ///
+    /// assert_eq!(types.name().as_deref(), Some("types"));
+    /// assert_eq!(format!("{:?}", id), "types#0");
+    /// ```
+    ///
+    /// # Features
+    ///
+    /// This API requires the `std` feature of this crate to be enabled.
+    pub fn with_name(name: impl Into<String>) -> Arena<T, A> {
+        let arena = Arena::new();
+        set_arena_name(arena.arena_id, name.into());
+        arena
+    }
+
+    /// Get this arena's name, if it was constructed with `Arena::with_name`.
+    ///
+    /// # Features
+    ///
+    /// This API requires the `std` feature of this crate to be enabled.
+    pub fn name(&self) -> Option<String> {
+        arena_name(self.arena_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Deterministic = DeterministicArenaBehavior<u32>;
+
+    #[test]
+    fn deterministic_names_are_per_thread() {
+        DeterministicArenaBehavior::<u32>::reset();
+        let named = Arena::<u32, Deterministic>::with_name("here");
+
+        std::thread::spawn(move || {
+            // This thread's first deterministic arena has the same arena id.
+            let unnamed = Arena::<u32, Deterministic>::new();
+            assert_eq!(unnamed.arena_id, named.arena_id);
+            assert_eq!(unnamed.name(), None);
+
+            let there = Arena::<u32, Deterministic>::with_name("there");
+            assert_eq!(there.name().as_deref(), Some("there"));
+            DeterministicArenaBehavior::<u32>::reset();
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(named.name().as_deref(), Some("here"));
+        assert_eq!(std::format!("{:?}", named.next_id()), "here#0");
+    }
+
+    #[test]
+    fn reused_arena_ids_forget_names() {
+        let named = Arena::<u32, Deterministic>::with_name("deterministic");
+        let explicit = Arena::<u32>::with_arena_id(named.arena_id & !EXPLICIT_ARENA_ID);
+        assert_eq!(explicit.name(), None);
+    }
+}
@@ -0,0 +1,370 @@
+use super::*;
+
+/// The number of items in each of a `PersistentArena`'s pages.
+const PAGE_SIZE: usize = 32;
+
+/// The number of pages in each of a `PersistentArena`'s chunks.
+const CHUNK_SIZE: usize = 32;
+
+type Page<T> = Arc<Vec<T>>;
+type Chunk<T> = Arc<Vec<Page<T>>>;
+
+/// An arena of objects of type `T` whose clones share structure.
+///
+/// Items are stored in fixed-size pages, which are grouped into fixed-size
+/// chunks, and both are shared between clones with reference counting, so
+/// cloning a `PersistentArena` is `O(1)`. Mutating an arena whose pages are
+/// shared with other clones copies only the page that is touched, its chunk's
+/// table of page pointers and the table of chunk pointers, leaving the other
+/// clones unchanged. This makes it cheap to keep many versions, or snapshots, of an
+/// arena around.
+///
+/// All snapshots of an arena share its arena id, so an id is valid in every
+/// snapshot that contains its item.
+///
+/// ```
+/// use id_arena::PersistentArena;
+///
+/// let mut arena = PersistentArena::<&str>::new();
+/// let a = arena.alloc("Albert");
+///
+/// let snapshot = arena.clone();
+/// arena.set(a, "Alice");
+/// let b = arena.alloc("Bob");
+///
+/// assert_eq!(arena[a], "Alice");
+/// assert_eq!(snapshot[a], "Albert");
+/// assert!(snapshot.get(b).is_none());
+/// ```
+pub struct PersistentArena<T, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    chunks: Arc<Vec<Chunk<T>>>,
+    len: usize,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<T, A> fmt::Debug for PersistentArena<T, A>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PersistentArena")
+            .field("arena_id", &self.arena_id)
+            .field("chunks", &self.chunks)
+            .finish()
+    }
+}
+
+impl<T, A> Clone for PersistentArena<T, A> {
+    #[inline]
+    fn clone(&self) -> PersistentArena<T, A> {
+        PersistentArena {
+            arena_id: self.arena_id,
+            chunks: self.chunks.clone(),
+            len: self.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Default for PersistentArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn default() -> PersistentArena<T, A> {
+        PersistentArena {
+            arena_id: A::new_arena_id(),
+            chunks: Arc::new(Vec::new()),
+            len: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> From<Arena<T, A>> for PersistentArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Convert an `Arena` into a `PersistentArena` with the same arena id, so
+    /// that the arena's ids remain valid.
+    fn from(arena: Arena<T, A>) -> PersistentArena<T, A> {
+        let len = arena.items.len();
+        let mut chunks =
+            Vec::with_capacity((len + PAGE_SIZE * CHUNK_SIZE - 1) / (PAGE_SIZE * CHUNK_SIZE));
+        let mut items = arena.items.into_iter().peekable();
+        while items.peek().is_some() {
+            let mut pages = Vec::with_capacity(CHUNK_SIZE);
+            while pages.len() < CHUNK_SIZE && items.peek().is_some() {
+                pages.push(Arc::new(items.by_ref().take(PAGE_SIZE).collect()));
+            }
+            chunks.push(Arc::new(pages));
+        }
+        PersistentArena {
+            arena_id: arena.arena_id,
+            chunks: Arc::new(chunks),
+            len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> PersistentArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `PersistentArena`.
+    #[inline]
+    pub fn new() -> PersistentArena<T, A> {
+        Default::default()
+    }
+
+    /// Allocate `item` within this arena and return its id.
+    ///
+    /// Other snapshots of this arena are unaffected.
+    pub fn alloc(&mut self, item: T) -> A::Id
+    where
+        T: Clone,
+    {
+        let id = self.next_id();
+        let chunks = Arc::make_mut(&mut self.chunks);
+        if self.len % (PAGE_SIZE * CHUNK_SIZE) == 0 {
+            chunks.push(Arc::new(Vec::with_capacity(CHUNK_SIZE)));
+        }
+        let chunk = chunks
+            .last_mut()
+            .expect("there is always a chunk with room");
+        let pages = Arc::make_mut(chunk);
+        if self.len % PAGE_SIZE == 0 {
+            pages.push(Arc::new(Vec::with_capacity(PAGE_SIZE)));
+        }
+        let page = pages.last_mut().expect("there is always a page with room");
+        Arc::make_mut(page).push(item);
+        self.len += 1;
+        id
+    }
+
+    /// Get the id that will be used for the next item allocated into this
+    /// arena.
+    #[inline]
+    pub fn next_id(&self) -> A::Id {
+        A::new_id(self.arena_id, self.len)
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists in this snapshot.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        self.try_get(id).ok()
+    }
+
+    /// Get a shared reference to the object associated with the given `id`, or
+    /// an error describing why there is none.
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&T, ArenaError> {
+        let index = check_id(self.arena_id, self.len, A::arena_id(id), A::index(id))?;
+        Ok(&self.page(index)[index % PAGE_SIZE])
+    }
+
+    #[inline]
+    pub(crate) fn page(&self, index: usize) -> &Page<T> {
+        let page = index / PAGE_SIZE;
+        &self.chunks[page / CHUNK_SIZE][page % CHUNK_SIZE]
+    }
+
+    /// Get an exclusive reference to the object associated with the given `id`
+    /// if it exists in this snapshot.
+    ///
+    /// If the item's page is shared with other snapshots, then the page and the
+    /// tables of pointers that lead to it are copied first, so that other
+    /// snapshots are unaffected.
+    pub fn get_mut(&mut self, id: A::Id) -> Option<&mut T>
+    where
+        T: Clone,
+    {
+        let index = check_id(self.arena_id, self.len, A::arena_id(id), A::index(id)).ok()?;
+        let page = index / PAGE_SIZE;
+        let chunk = &mut Arc::make_mut(&mut self.chunks)[page / CHUNK_SIZE];
+        let page = &mut Arc::make_mut(chunk)[page % CHUNK_SIZE];
+        Some(&mut Arc::make_mut(page)[index % PAGE_SIZE])
+    }
+
+    /// Replace the object associated with the given `id` with `item`, and
+    /// return the old object.
+    ///
+    /// Other snapshots of this arena are unaffected.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no object associated with `id` in this snapshot.
+    #[track_caller]
+    pub fn set(&mut self, id: A::Id, item: T) -> T
+    where
+        T: Clone,
+    {
+        core::mem::replace(&mut self[id], item)
+    }
+
+    /// Iterate over this arena's items and their ids.
+    #[inline]
+    pub fn iter(&self) -> PersistentIter<'_, T, A> {
+        IntoIterator::into_iter(self)
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T, A> ops::Index<A::Id> for PersistentArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        match self.try_get(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+impl<T, A> ops::IndexMut<A::Id> for PersistentArena<T, A>
+where
+    A: ArenaBehavior,
+    T: Clone,
+{
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, id: A::Id) -> &mut T {
+        if let Err(err) = self.try_get(id) {
+            index_panic::<T>(err);
+        }
+        self.get_mut(id).expect("id was just checked")
+    }
+}
+
+/// An iterator over `(Id, &T)` pairs in a `PersistentArena`.
+///
+/// See [the `PersistentArena::iter()`
+/// method](./struct.PersistentArena.html#method.iter) for details.
+#[derive(Debug)]
+pub struct PersistentIter<'a, T: 'a, A: 'a> {
+    arena_id: u32,
+    index: usize,
+    remaining: usize,
+    chunks: slice::Iter<'a, Chunk<T>>,
+    pages: slice::Iter<'a, Page<T>>,
+    page: slice::Iter<'a, T>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for PersistentIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.page.next() {
+                self.index += 1;
+                self.remaining -= 1;
+                return Some((A::new_id(self.arena_id, self.index - 1), item));
+            }
+            match self.pages.next() {
+                Some(page) => self.page = page.iter(),
+                None => self.pages = self.chunks.next()?.iter(),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for PersistentIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a PersistentArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+    type IntoIter = PersistentIter<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> PersistentIter<'a, T, A> {
+        PersistentIter {
+            arena_id: self.arena_id,
+            index: 0,
+            remaining: self.len,
+            chunks: self.chunks.iter(),
+            pages: [].iter(),
+            page: [].iter(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persistent_set_copies_only_the_touched_page() {
+        let mut arena = PersistentArena::<usize>::from(Arena::from_iter(0..3000));
+        let snapshot = arena.clone();
+        let ids: Vec<_> = arena.iter().map(|(id, _)| id).collect();
+
+        arena.set(ids[1500], 0);
+
+        for index in 0..3000 {
+            let shared = Arc::ptr_eq(arena.page(index), snapshot.page(index));
+            assert_eq!(shared, index / 32 != 1500 / 32, "page of index {}", index);
+        }
+        assert_eq!(snapshot[ids[1500]], 1500);
+    }
+
+    #[test]
+    fn persistent_snapshots_are_independent() {
+        let mut arena = PersistentArena::<usize>::from(Arena::from_iter(0..70));
+        let ids: Vec<_> = arena.iter().map(|(id, _)| id).collect();
+        let snapshot = arena.clone();
+
+        for &id in ids.iter().step_by(3) {
+            arena[id] += 1000;
+        }
+        let new = arena.alloc(70);
+
+        assert!(snapshot.iter().all(|(id, &x)| x == id.index()));
+        assert_eq!(snapshot.iter().len(), 70);
+        assert!(snapshot.get(new).is_none());
+        assert!(arena.iter().all(|(id, &x)| {
+            x == id.index()
+                + if id.index() % 3 == 0 && id != new {
+                    1000
+                } else {
+                    0
+                }
+        }));
+    }
+}
@@ -0,0 +1,222 @@
+use super::*;
+
+use core::sync::atomic::AtomicU32;
+
+/// The number of low bits of a pooled arena id that hold its slot.
+const SLOT_BITS: u32 = 12;
+
+/// The number of pooled arena id slots.
+const SLOTS: usize = 1 << SLOT_BITS;
+
+/// The mask of the bits of a pooled arena id that hold its slot's generation.
+const GENERATION_MASK: u32 = (POOLED_ARENA_ID - 1) >> SLOT_BITS;
+
+/// The bit that is set in a slot's state while an arena uses the slot.
+const IN_USE: u32 = 1;
+
+/// The state of every slot: its generation, shifted left by one, and `IN_USE`.
+#[allow(clippy::declare_interior_mutable_const)]
+const FREE_SLOT: AtomicU32 = AtomicU32::new(0);
+static SLOT_STATES: [AtomicU32; SLOTS] = [FREE_SLOT; SLOTS];
+
+/// The slot that the next pooled arena tries first.
+static NEXT_SLOT: AtomicU32 = AtomicU32::new(0);
+
+/// The number of slots that are in use.
+static SLOTS_IN_USE: AtomicU32 = AtomicU32::new(0);
+
+/// A pool of arena ids that can be safely reused once their arenas are
+/// retired.
+///
+/// The default `ArenaBehavior::new_arena_id` hands out every arena id at most
+/// once, and panics when it runs out. A long-running program that creates
+/// many short-lived arenas can instead create them through an `ArenaIdPool`,
+/// and hand each arena back with `ArenaIdPool::recycle` when it is done with
+/// it, so that its arena id's slot can be reused by the next arena.
+///
+/// Pooled arena ids are kept apart from the arena ids of `Arena::new` and
+/// `Arena::with_arena_id`, and consist of one of 4096 slots and that slot's
+/// generation. Recycling an arena bumps its slot's generation, so the ids of
+/// the retired arena, and of any clones of it, do not belong to the next arena
+/// in that slot: using them with it is an `ArenaError::WrongArena`, as with
+/// any other arena. A stale id could only alias a new arena after its slot has
+/// been recycled `2^18` times. When all slots are in use, the pool falls back
+/// to `A::new_arena_id()`.
+///
+/// Slots are shared by all pools, so dropping a pool does not leak them.
+///
+/// The pool creates `Arena`s, but the arenas that wrap an `Arena`, such as
+/// `TrackedArena`, `VersionedArena`, `JournaledArena` and `IndexedArena`, can
+/// be created from them with `From`, and recycled through their `into_inner`
+/// methods.
+///
+/// ```
+/// use id_arena::{Arena, ArenaIdPool};
+///
+/// let pool = ArenaIdPool::new();
+///
+/// let mut arena: Arena<u32> = pool.new_arena();
+/// let id = arena.alloc(42);
+/// assert_eq!(arena[id], 42);
+/// assert!(pool.recycle(arena));
+///
+/// // Ids of the recycled arena do not belong to the next arena.
+/// let mut arena: Arena<u32> = pool.new_arena();
+/// arena.alloc(7);
+/// assert!(arena.get(id).is_none());
+/// ```
+///
+/// # Features
+///
+/// This API is only available on targets with 32-bit atomics.
+#[derive(Clone, Debug, Default)]
+pub struct ArenaIdPool {
+    _private: (),
+}
+
+impl ArenaIdPool {
+    /// Construct a new `ArenaIdPool`.
+    #[inline]
+    pub fn new() -> ArenaIdPool {
+        Default::default()
+    }
+
+    /// Construct a new, empty `Arena` with a pooled arena id.
+    ///
+    /// When all pooled arena id slots are in use, a fresh arena id is created
+    /// with `A::new_arena_id()` instead.
+    pub fn new_arena<T, A>(&self) -> Arena<T, A>
+    where
+        A: ArenaBehavior,
+    {
+        let arena_id = claim_slot().unwrap_or_else(A::new_arena_id);
+        Arena {
+            arena_id,
+            items: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Drop `arena` and make its arena id's slot available for reuse.
+    ///
+    /// Returns `true` if the slot was recycled, and `false` if `arena` did not
+    /// come from a pool, or if its slot was already recycled, e.g. through a
+    /// clone of `arena`. The slot's next arena has a new arena id, so any ids
+    /// from `arena` that are still around are rejected by it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `arena` has an explicit arena id, e.g. from
+    /// `Arena::with_arena_id` or `DeterministicArenaBehavior`: such arena ids
+    /// are chosen by the caller, and are never the pool's to reuse.
+    pub fn recycle<T, A>(&self, arena: Arena<T, A>) -> bool
+    where
+        A: ArenaBehavior,
+    {
+        let arena_id = arena.arena_id;
+        assert!(
+            arena_id & EXPLICIT_ARENA_ID == 0,
+            "cannot recycle arena {}: its arena id is explicit",
+            ArenaLabel(arena_id)
+        );
+        if arena_id & POOLED_ARENA_ID == 0 {
+            return false;
+        }
+
+        let slot = arena_id as usize & (SLOTS - 1);
+        let generation = (arena_id >> SLOT_BITS) & GENERATION_MASK;
+        let in_use = (generation << 1) | IN_USE;
+        let free = next_generation(generation) << 1;
+        let recycled = SLOT_STATES[slot]
+            .compare_exchange(
+                in_use,
+                free,
+                atomic::Ordering::SeqCst,
+                atomic::Ordering::SeqCst,
+            )
+            .is_ok();
+        if recycled {
+            SLOTS_IN_USE.fetch_sub(1, atomic::Ordering::SeqCst);
+            NEXT_SLOT.store(slot as u32, atomic::Ordering::SeqCst);
+        }
+        recycled
+    }
+
+    /// Get the number of pooled arena id slots that are available.
+    #[inline]
+    pub fn available(&self) -> usize {
+        SLOTS - SLOTS_IN_USE.load(atomic::Ordering::SeqCst) as usize
+    }
+}
+
+fn next_generation(generation: u32) -> u32 {
+    generation.wrapping_add(1) & GENERATION_MASK
+}
+
+/// Claim a free slot, and return its arena id.
+fn claim_slot() -> Option<u32> {
+    if SLOTS_IN_USE.load(atomic::Ordering::SeqCst) as usize >= SLOTS {
+        return None;
+    }
+    let start = NEXT_SLOT.load(atomic::Ordering::SeqCst) as usize;
+    (0..SLOTS).find_map(|offset| {
+        let slot = (start + offset) % SLOTS;
+        let state = SLOT_STATES[slot].load(atomic::Ordering::SeqCst);
+        if state & IN_USE != 0 {
+            return None;
+        }
+        SLOT_STATES[slot]
+            .compare_exchange(
+                state,
+                state | IN_USE,
+                atomic::Ordering::SeqCst,
+                atomic::Ordering::SeqCst,
+            )
+            .ok()?;
+        SLOTS_IN_USE.fetch_add(1, atomic::Ordering::SeqCst);
+        NEXT_SLOT.store(((slot + 1) % SLOTS) as u32, atomic::Ordering::SeqCst);
+        Some(POOLED_ARENA_ID | ((state >> 1) << SLOT_BITS) | slot as u32)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycled_arena_ids_reject_stale_ids() {
+        let pool = ArenaIdPool::new();
+        let mut arena: Arena<u32> = pool.new_arena();
+        assert_ne!(arena.arena_id & POOLED_ARENA_ID, 0);
+        let stale = arena.alloc(1);
+        let clone = arena.clone();
+        assert!(pool.recycle(arena));
+        assert!(!pool.recycle(clone));
+
+        for _ in 0..SLOTS {
+            let mut arena: Arena<u32> = pool.new_arena();
+            arena.alloc(2);
+            assert!(arena.get(stale).is_none());
+            assert!(pool.recycle(arena));
+        }
+    }
+
+    #[test]
+    fn arenas_that_are_not_pooled_are_not_recycled() {
+        let pool = ArenaIdPool::new();
+        assert!(!pool.recycle(Arena::<u32>::new()));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot recycle arena explicit-arena3: its arena id is explicit")]
+    fn explicit_arena_ids_cannot_be_recycled() {
+        ArenaIdPool::new().recycle(Arena::<u32>::with_arena_id(3));
+    }
+
+    #[test]
+    fn generations_wrap_around() {
+        assert_eq!(next_generation(0), 1);
+        assert_eq!(next_generation(GENERATION_MASK), 0);
+        assert_eq!(GENERATION_MASK, (1 << 18) - 1);
+    }
+}
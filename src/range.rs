@@ -0,0 +1,135 @@
+use super::*;
+
+/// A contiguous range of ids within one arena, such as the ids of a batch of
+/// objects that were allocated together.
+///
+/// An `IdRange` is an iterator over its ids, in order.
+///
+/// See [the `Arena::alloc_extend()` method](./struct.Arena.html#method.alloc_extend)
+/// for details.
+pub struct IdRange<T, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    start: usize,
+    end: usize,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A> fmt::Debug for IdRange<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IdRange")
+            .field("arena_id", &self.arena_id)
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .finish()
+    }
+}
+
+impl<T, A> Clone for IdRange<T, A> {
+    #[inline]
+    fn clone(&self) -> IdRange<T, A> {
+        IdRange {
+            arena_id: self.arena_id,
+            start: self.start,
+            end: self.end,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> PartialEq for IdRange<T, A> {
+    #[inline]
+    fn eq(&self, rhs: &Self) -> bool {
+        self.arena_id == rhs.arena_id && self.start == rhs.start && self.end == rhs.end
+    }
+}
+
+impl<T, A> Eq for IdRange<T, A> {}
+
+impl<T, A> IdRange<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    pub(crate) fn new(arena_id: u32, start: usize, end: usize) -> IdRange<T, A> {
+        IdRange {
+            arena_id,
+            start,
+            end,
+            _phantom: PhantomData,
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub(crate) fn arena_id(&self) -> u32 {
+        self.arena_id
+    }
+
+    /// Get the range of indices that this range's ids refer to.
+    #[inline]
+    pub fn indices(&self) -> ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns `true` if the given `id` is within this range.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<u32>::new();
+    /// let before = arena.alloc(0);
+    /// let range = arena.alloc_extend(vec![1, 2, 3]);
+    ///
+    /// assert!(!range.contains(before));
+    /// assert!(range.clone().all(|id| range.contains(id)));
+    /// ```
+    #[inline]
+    pub fn contains(&self, id: A::Id) -> bool {
+        A::arena_id(id) == self.arena_id && self.indices().contains(&A::index(id))
+    }
+}
+
+impl<T, A> Iterator for IdRange<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = A::Id;
+
+    #[inline]
+    fn next(&mut self) -> Option<A::Id> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(A::new_id(self.arena_id, self.start - 1))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T, A> DoubleEndedIterator for IdRange<T, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<A::Id> {
+        if self.start < self.end {
+            self.end -= 1;
+            Some(A::new_id(self.arena_id, self.end))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, A> ExactSizeIterator for IdRange<T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
@@ -15,13 +15,14 @@ where
     /// # Features
     ///
     /// This API requires the `rayon` feature of this crate to be enabled.
-    pub fn par_iter(&self) -> ParIter<T, A>
+    pub fn par_iter(&self) -> ParIter<'_, T, A>
     where
         T: Sync,
         A::Id: Send,
     {
         ParIter {
             arena_id: self.arena_id,
+            offset: 0,
             iter: self.items.par_iter().enumerate(),
             _phantom: PhantomData,
         }
@@ -33,17 +34,150 @@ where
     /// # Features
     ///
     /// This API requires the `rayon` feature of this crate to be enabled.
-    pub fn par_iter_mut(&mut self) -> ParIterMut<T, A>
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, T, A>
     where
         T: Send + Sync,
         A::Id: Send,
     {
         ParIterMut {
             arena_id: self.arena_id,
+            offset: 0,
             iter: self.items.par_iter_mut().enumerate(),
             _phantom: PhantomData,
         }
     }
+
+    /// Returns an iterator of shared references to the items in the given
+    /// `range`, which can be used to iterate over them in parallel with the
+    /// `rayon` crate.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut arena = Arena::<u32>::new();
+    /// arena.alloc(1);
+    /// let range = arena.alloc_extend(vec![2, 3, 4]);
+    ///
+    /// let sum: u32 = arena.par_iter_range(range).map(|(_, x)| x).sum();
+    /// assert_eq!(sum, 9);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `range` is not within this arena.
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    #[track_caller]
+    pub fn par_iter_range(&self, range: IdRange<T, A>) -> ParIter<'_, T, A>
+    where
+        T: Sync,
+        A::Id: Send,
+    {
+        let indices = self.range_indices(range);
+        ParIter {
+            arena_id: self.arena_id,
+            offset: indices.start,
+            iter: self.items[indices].par_iter().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns an iterator of mutable references to the items in the given
+    /// `range`, which can be used to iterate over them in parallel with the
+    /// `rayon` crate.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `range` is not within this arena.
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    #[track_caller]
+    pub fn par_iter_mut_range(&mut self, range: IdRange<T, A>) -> ParIterMut<'_, T, A>
+    where
+        T: Send + Sync,
+        A::Id: Send,
+    {
+        let indices = self.range_indices(range);
+        ParIterMut {
+            arena_id: self.arena_id,
+            offset: indices.start,
+            iter: self.items[indices].par_iter_mut().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns an iterator of shared references to the items with the given
+    /// `ids`, which can be used to iterate over them in parallel with the
+    /// `rayon` crate.
+    ///
+    /// The items are produced in index order.
+    ///
+    /// ```
+    /// use id_arena::{Arena, IdSet};
+    /// use rayon::prelude::*;
+    ///
+    /// let mut arena = Arena::<u32>::new();
+    /// let a = arena.alloc(1);
+    /// arena.alloc(2);
+    /// let c = arena.alloc(3);
+    ///
+    /// let ids: IdSet<u32> = [a, c].into_iter().collect();
+    /// let items: Vec<_> = arena.par_iter_ids(&ids).map(|(_, x)| *x).collect();
+    /// assert_eq!(items, [1, 3]);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if any of the `ids` are not within this arena.
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    #[track_caller]
+    pub fn par_iter_ids(&self, ids: &IdSet<T, A>) -> ParIterIds<'_, T, A>
+    where
+        T: Sync,
+        A::Id: Send,
+    {
+        let indices: Vec<usize> = ids
+            .iter()
+            .map(|id| match self.try_get(id) {
+                Ok(_) => A::index(id),
+                Err(err) => index_panic::<T>(err),
+            })
+            .collect();
+        ParIterIds {
+            arena_id: self.arena_id,
+            items: &self.items,
+            indices: indices.into_par_iter(),
+            _phantom: PhantomData,
+        }
+    }
+
+    #[track_caller]
+    fn range_indices(&self, range: IdRange<T, A>) -> ops::Range<usize> {
+        let indices = range.indices();
+        let err = if range.arena_id() != self.arena_id {
+            ArenaError::WrongArena {
+                expected: self.arena_id,
+                found: range.arena_id(),
+                index: Some(indices.start),
+            }
+        } else if indices.end > self.len() {
+            ArenaError::OutOfBounds {
+                index: indices.end - 1,
+                len: self.len(),
+            }
+        } else {
+            return indices;
+        };
+        index_panic::<T>(err)
+    }
 }
 
 /// A parallel iterator over shared references in an arena.
@@ -55,6 +189,7 @@ where
     T: Sync,
 {
     arena_id: u32,
+    offset: usize,
     iter: rayon::iter::Enumerate<rayon::slice::Iter<'a, T>>,
     _phantom: PhantomData<fn() -> A>,
 }
@@ -71,8 +206,8 @@ where
     where
         C: UnindexedConsumer<Self::Item>,
     {
-        let arena_id = self.arena_id;
-        self.iter.map(|(i, item)| (A::new_id(arena_id, i), item))
+        let (arena_id, offset) = (self.arena_id, self.offset);
+        self.iter.map(|(i, item)| (A::new_id(arena_id, offset + i), item))
             .drive_unindexed(consumer)
     }
 
@@ -91,8 +226,8 @@ where
     where
         C: Consumer<Self::Item>,
     {
-        let arena_id = self.arena_id;
-        self.iter.map(|(i, item)| (A::new_id(arena_id, i), item))
+        let (arena_id, offset) = (self.arena_id, self.offset);
+        self.iter.map(|(i, item)| (A::new_id(arena_id, offset + i), item))
             .drive(consumer)
     }
 
@@ -104,8 +239,8 @@ where
     where
         CB: ProducerCallback<Self::Item>,
     {
-        let arena_id = self.arena_id;
-        self.iter.map(|(i, item)| (A::new_id(arena_id, i), item))
+        let (arena_id, offset) = (self.arena_id, self.offset);
+        self.iter.map(|(i, item)| (A::new_id(arena_id, offset + i), item))
             .with_producer(callback)
     }
 }
@@ -132,6 +267,7 @@ where
     T: Send + Sync,
 {
     arena_id: u32,
+    offset: usize,
     iter: rayon::iter::Enumerate<rayon::slice::IterMut<'a, T>>,
     _phantom: PhantomData<fn() -> A>,
 }
@@ -148,8 +284,8 @@ where
     where
         C: UnindexedConsumer<Self::Item>,
     {
-        let arena_id = self.arena_id;
-        self.iter.map(|(i, item)| (A::new_id(arena_id, i), item))
+        let (arena_id, offset) = (self.arena_id, self.offset);
+        self.iter.map(|(i, item)| (A::new_id(arena_id, offset + i), item))
             .drive_unindexed(consumer)
     }
 
@@ -168,8 +304,8 @@ where
     where
         C: Consumer<Self::Item>,
     {
-        let arena_id = self.arena_id;
-        self.iter.map(|(i, item)| (A::new_id(arena_id, i), item))
+        let (arena_id, offset) = (self.arena_id, self.offset);
+        self.iter.map(|(i, item)| (A::new_id(arena_id, offset + i), item))
             .drive(consumer)
     }
 
@@ -181,8 +317,8 @@ where
     where
         CB: ProducerCallback<Self::Item>,
     {
-        let arena_id = self.arena_id;
-        self.iter.map(|(i, item)| (A::new_id(arena_id, i), item))
+        let (arena_id, offset) = (self.arena_id, self.offset);
+        self.iter.map(|(i, item)| (A::new_id(arena_id, offset + i), item))
             .with_producer(callback)
     }
 }
@@ -280,3 +416,275 @@ impl<T, A> IntoParallelIterator for Arena<T, A>
         }
     }
 }
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+    T: Send,
+{
+    /// Allocate every item from the parallel iterator `items` within this
+    /// arena, in order, and return the range of their ids.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut arena = Arena::<u64>::new();
+    /// let squares = arena.par_alloc_extend((0..1000u64).into_par_iter().map(|x| x * x));
+    ///
+    /// assert_eq!(squares.len(), 1000);
+    /// assert!(squares.enumerate().all(|(x, id)| arena[id] == (x * x) as u64));
+    /// ```
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    pub fn par_alloc_extend<I>(&mut self, items: I) -> IdRange<T, A>
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let start = self.items.len();
+        self.items.par_extend(items);
+        IdRange::new(self.arena_id, start, self.items.len())
+    }
+}
+
+impl<T, A> ParallelExtend<T> for Arena<T, A>
+where
+    A: ArenaBehavior,
+    T: Send,
+{
+    fn par_extend<I>(&mut self, items: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        self.items.par_extend(items);
+    }
+}
+
+impl<T, A> FromParallelIterator<T> for Arena<T, A>
+where
+    A: ArenaBehavior,
+    T: Send,
+{
+    fn from_par_iter<I>(items: I) -> Self
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        Arena {
+            arena_id: A::new_arena_id(),
+            items: Vec::from_par_iter(items),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// A parallel iterator over shared references to a subset of an arena's
+/// items.
+///
+/// See `Arena::par_iter_ids` for more information.
+#[derive(Debug)]
+pub struct ParIterIds<'a, T, A>
+where
+    T: Sync,
+{
+    arena_id: u32,
+    items: &'a [T],
+    indices: rayon::vec::IntoIter<usize>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T, A> ParallelIterator for ParIterIds<'a, T, A>
+where
+    T: Sync,
+    A: ArenaBehavior,
+    A::Id: Send,
+{
+    type Item = (A::Id, &'a T);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let (arena_id, items) = (self.arena_id, self.items);
+        self.indices
+            .map(move |i| (A::new_id(arena_id, i), &items[i]))
+            .drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        self.indices.opt_len()
+    }
+}
+
+impl<'a, T, A> IndexedParallelIterator for ParIterIds<'a, T, A>
+where
+    T: Sync,
+    A: ArenaBehavior,
+    A::Id: Send,
+{
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        let (arena_id, items) = (self.arena_id, self.items);
+        self.indices
+            .map(move |i| (A::new_id(arena_id, i), &items[i]))
+            .drive(consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        let (arena_id, items) = (self.arena_id, self.items);
+        self.indices
+            .map(move |i| (A::new_id(arena_id, i), &items[i]))
+            .with_producer(callback)
+    }
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+    A::Id: Send,
+    T: Send,
+{
+    /// Transform every item in this arena in parallel, producing a new arena
+    /// whose ids correspond index-for-index to this arena's ids.
+    ///
+    /// See `Arena::map` for details.
+    ///
+    /// ```
+    /// use id_arena::{Arena, DefaultArenaBehavior};
+    ///
+    /// let mut numbers = Arena::<u32>::new();
+    /// let ids = numbers.alloc_extend(0..100);
+    ///
+    /// let strings: Arena<String> = numbers.par_map(|_, n| n.to_string());
+    /// for (n, id) in ids.enumerate() {
+    ///     let id = strings.convert_id::<DefaultArenaBehavior<u32>>(id).unwrap();
+    ///     assert_eq!(strings[id], n.to_string());
+    /// }
+    /// ```
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    pub fn par_map<U, B, F>(self, f: F) -> Arena<U, B>
+    where
+        B: ArenaBehavior,
+        U: Send,
+        F: Fn(A::Id, T) -> U + Sync + Send,
+    {
+        Arena {
+            arena_id: self.arena_id,
+            items: self
+                .into_par_iter()
+                .map(|(id, item)| f(id, item))
+                .collect(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+    T: Sync,
+{
+    /// Stably sort this arena's items by the key that `f` extracts, comparing
+    /// keys in parallel, and return a mapping from the items' old ids to their
+    /// new ids.
+    ///
+    /// See `Arena::sort_by_key` for details.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<u32>::new();
+    /// let ids: Vec<_> = arena.alloc_extend((0..1000).rev()).collect();
+    ///
+    /// let remap = arena.par_sort_by_key(|&x| x);
+    ///
+    /// assert!(arena.iter().all(|(id, &x)| id.index() == x as usize));
+    /// assert_eq!(remap.apply(ids[0]).index(), 999);
+    /// ```
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    pub fn par_sort_by_key<K, F>(&mut self, f: F) -> Remap<T, A>
+    where
+        K: Ord + Send,
+        F: Fn(&T) -> K + Sync,
+    {
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        let items = &self.items;
+        order.par_sort_by_key(|&i| f(&items[i]));
+        self.permute(&order)
+    }
+}
+
+impl<T, A> FrozenArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Returns an iterator of shared references which can be used to iterate
+    /// over this frozen arena in parallel with the `rayon` crate.
+    ///
+    /// # Features
+    ///
+    /// This API requires the `rayon` feature of this crate to be enabled.
+    pub fn par_iter(&self) -> ParIter<'_, T, A>
+    where
+        T: Sync,
+        A::Id: Send,
+    {
+        self.arena.par_iter()
+    }
+}
+
+impl<'data, T, A> IntoParallelIterator for &'data FrozenArena<T, A>
+where
+    A: ArenaBehavior,
+    A::Id: Send,
+    T: Sync,
+{
+    type Item = (A::Id, &'data T);
+    type Iter = ParIter<'data, T, A>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "cannot index arena of `u32`: id arena")]
+    fn par_iter_range_rejects_empty_ranges_of_other_arenas() {
+        let mut other = Arena::<u32>::new();
+        let range = other.alloc_extend(std::vec![]);
+        let mut arena = Arena::<u32>::new();
+        arena.alloc(0);
+        let _ = arena.par_iter_range(range);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index arena of `u32`: index 2 is out of bounds for length 1")]
+    fn par_iter_range_rejects_empty_ranges_out_of_bounds() {
+        let mut arena = Arena::<u32>::new();
+        arena.alloc(0);
+        let mut longer = arena.clone();
+        longer.alloc_extend(std::vec![1, 2]);
+        let range = longer.alloc_extend(std::vec![]);
+        let _ = arena.par_iter_mut_range(range);
+    }
+}
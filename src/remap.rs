@@ -0,0 +1,229 @@
+use super::*;
+
+/// A mapping from an arena's old ids to its new ids, after the arena's items
+/// were reordered.
+///
+/// See [the `Arena::sort_by_key()` method](./struct.Arena.html#method.sort_by_key)
+/// for details.
+pub struct Remap<T, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    new_indices: Vec<usize>,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A> fmt::Debug for Remap<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Remap")
+            .field("arena_id", &self.arena_id)
+            .field("new_indices", &self.new_indices)
+            .finish()
+    }
+}
+
+impl<T, A> Clone for Remap<T, A> {
+    #[inline]
+    fn clone(&self) -> Remap<T, A> {
+        Remap {
+            arena_id: self.arena_id,
+            new_indices: self.new_indices.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Remap<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Get the new id of the item that had the given old id, if `old` belongs
+    /// to the reordered arena.
+    #[inline]
+    pub fn get(&self, old: A::Id) -> Option<A::Id> {
+        let index = check_id(
+            self.arena_id,
+            self.new_indices.len(),
+            A::arena_id(old),
+            A::index(old),
+        )
+        .ok()?;
+        Some(A::new_id(self.arena_id, self.new_indices[index]))
+    }
+
+    /// Get the new id of the item that had the given old id.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `old` does not belong to the reordered arena.
+    #[inline]
+    #[track_caller]
+    pub fn apply(&self, old: A::Id) -> A::Id {
+        match check_id(
+            self.arena_id,
+            self.new_indices.len(),
+            A::arena_id(old),
+            A::index(old),
+        ) {
+            Ok(index) => A::new_id(self.arena_id, self.new_indices[index]),
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+
+    /// Rewrite every id of the reordered arena within `value` to its new id.
+    ///
+    /// Ids that belong to other arenas are left alone.
+    pub fn rewrite<V>(&self, value: &mut V)
+    where
+        V: VisitIds<A> + ?Sized,
+    {
+        value.visit_ids(&mut |id| {
+            if let Some(new) = self.get(*id) {
+                *id = new;
+            }
+        });
+    }
+
+    /// Get the number of ids in this mapping, which is the number of items in
+    /// the reordered arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.new_indices.len()
+    }
+
+    /// Returns `true` if this mapping is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.new_indices.is_empty()
+    }
+}
+
+/// A type whose values contain ids that need to be rewritten when the arena
+/// the ids belong to is reordered.
+///
+/// See [the `Arena::rewrite_ids()` method](./struct.Arena.html#method.rewrite_ids)
+/// for details.
+pub trait VisitIds<A>
+where
+    A: ArenaBehavior,
+{
+    /// Call `f` with a mutable reference to every id inside `self`.
+    fn visit_ids(&mut self, f: &mut dyn FnMut(&mut A::Id));
+}
+
+impl<T, A> Arena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Stably sort this arena's items by the key that `f` extracts, and return
+    /// a mapping from the items' old ids to their new ids.
+    ///
+    /// ```
+    /// use id_arena::Arena;
+    ///
+    /// let mut arena = Arena::<&str>::new();
+    /// let c = arena.alloc("c");
+    /// let a = arena.alloc("a");
+    /// let b = arena.alloc("b");
+    ///
+    /// let remap = arena.sort_by_key(|s| *s);
+    ///
+    /// assert_eq!(arena.iter().map(|(_, s)| *s).collect::<String>(), "abc");
+    /// assert_eq!(arena[remap.apply(a)], "a");
+    /// assert_eq!(arena[remap.apply(b)], "b");
+    /// assert_eq!(arena[remap.apply(c)], "c");
+    /// ```
+    pub fn sort_by_key<K, F>(&mut self, mut f: F) -> Remap<T, A>
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        order.sort_by_key(|&i| f(&self.items[i]));
+        self.permute(&order)
+    }
+
+    /// Rewrite every id of this arena within this arena's items, using the
+    /// mapping returned from reordering the arena.
+    ///
+    /// ```
+    /// use id_arena::{Arena, DefaultArenaBehavior, Id, VisitIds};
+    ///
+    /// struct Node {
+    ///     name: &'static str,
+    ///     next: Option<Id<Node>>,
+    /// }
+    ///
+    /// impl VisitIds<DefaultArenaBehavior<Node>> for Node {
+    ///     fn visit_ids(&mut self, f: &mut dyn FnMut(&mut Id<Node>)) {
+    ///         if let Some(next) = &mut self.next {
+    ///             f(next);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut arena = Arena::<Node>::new();
+    /// let b = arena.alloc(Node { name: "b", next: None });
+    /// let a = arena.alloc(Node { name: "a", next: Some(b) });
+    ///
+    /// let remap = arena.sort_by_key(|node| node.name);
+    /// arena.rewrite_ids(&remap);
+    ///
+    /// let a = remap.apply(a);
+    /// let next = arena[a].next.unwrap();
+    /// assert_eq!(arena[next].name, "b");
+    /// ```
+    pub fn rewrite_ids(&mut self, remap: &Remap<T, A>)
+    where
+        T: VisitIds<A>,
+    {
+        for item in &mut self.items {
+            remap.rewrite(item);
+        }
+    }
+
+    /// Move each item from index `order[i]` to index `i`.
+    pub(crate) fn permute(&mut self, order: &[usize]) -> Remap<T, A> {
+        debug_assert_eq!(order.len(), self.items.len());
+        // `order` is a permutation, so every element is overwritten below.
+        let mut new_indices = order.to_vec();
+        for (new, &old) in order.iter().enumerate() {
+            new_indices[old] = new;
+        }
+
+        // Follow each cycle of the permutation, swapping every item into its
+        // place.
+        let mut pending = new_indices.clone();
+        for i in 0..pending.len() {
+            while pending[i] != i {
+                let j = pending[i];
+                self.items.swap(i, j);
+                pending.swap(i, j);
+            }
+        }
+
+        Remap {
+            arena_id: self.arena_id,
+            new_indices,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_by_key_remaps_every_id() {
+        let mut arena = Arena::<u32>::new();
+        let ids: Vec<_> = arena.alloc_extend(0..100).collect();
+
+        let remap = arena.sort_by_key(|&x| (x * 37) % 100);
+
+        assert!(arena
+            .iter()
+            .all(|(id, &x)| (x * 37) % 100 == id.index() as u32));
+        for (x, &id) in ids.iter().enumerate() {
+            assert_eq!(arena[remap.apply(id)], x as u32);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use super::*;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A set of ids from one arena, stored as a bit set over their indices.
+///
+/// The set learns which arena it belongs to from the first id that is inserted
+/// into it, and inserting an id from any other arena panics.
+///
+/// ```
+/// use id_arena::{Arena, IdSet};
+///
+/// let mut arena = Arena::<&str>::new();
+/// let a = arena.alloc("a");
+/// let b = arena.alloc("b");
+/// let c = arena.alloc("c");
+///
+/// let mut set = IdSet::<&str>::new();
+/// set.insert(c);
+/// set.insert(a);
+///
+/// assert!(set.contains(a));
+/// assert!(!set.contains(b));
+/// assert_eq!(set.iter().collect::<Vec<_>>(), [a, c]);
+/// ```
+pub struct IdSet<T, A = DefaultArenaBehavior<T>> {
+    arena_id: Option<u32>,
+    words: Vec<u64>,
+    len: usize,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A> fmt::Debug for IdSet<T, A>
+where
+    A: ArenaBehavior,
+    A::Id: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T, A> Clone for IdSet<T, A> {
+    #[inline]
+    fn clone(&self) -> IdSet<T, A> {
+        IdSet {
+            arena_id: self.arena_id,
+            words: self.words.clone(),
+            len: self.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Default for IdSet<T, A> {
+    #[inline]
+    fn default() -> IdSet<T, A> {
+        IdSet {
+            arena_id: None,
+            words: Vec::new(),
+            len: 0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> PartialEq for IdSet<T, A> {
+    fn eq(&self, rhs: &Self) -> bool {
+        if self.len != rhs.len {
+            return false;
+        }
+        if self.len == 0 {
+            return true;
+        }
+        let (short, long) = if self.words.len() <= rhs.words.len() {
+            (&self.words, &rhs.words)
+        } else {
+            (&rhs.words, &self.words)
+        };
+        self.arena_id == rhs.arena_id
+            && long[..short.len()] == short[..]
+            && long[short.len()..].iter().all(|&word| word == 0)
+    }
+}
+
+impl<T, A> Eq for IdSet<T, A> {}
+
+impl<T, A> IdSet<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `IdSet`.
+    #[inline]
+    pub fn new() -> IdSet<T, A> {
+        Default::default()
+    }
+
+    /// Insert `id` into this set, and return `true` if it was not already
+    /// present.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `id` is from a different arena than the ids already inserted
+    /// into this set.
+    #[track_caller]
+    pub fn insert(&mut self, id: A::Id) -> bool {
+        let arena_id = *self.arena_id.get_or_insert(A::arena_id(id));
+        if arena_id != A::arena_id(id) {
+            panic!(
+                "cannot insert into `IdSet`: {}",
+                ArenaError::WrongArena {
+                    expected: arena_id,
+                    found: A::arena_id(id),
+                    index: Some(A::index(id)),
+                }
+            );
+        }
+        let (word, mask) = Self::position(A::index(id));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let inserted = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        self.len += inserted as usize;
+        inserted
+    }
+
+    /// Remove `id` from this set, and return `true` if it was present.
+    pub fn remove(&mut self, id: A::Id) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+        let (word, mask) = Self::position(A::index(id));
+        self.words[word] &= !mask;
+        self.len -= 1;
+        true
+    }
+
+    /// Returns `true` if `id` is in this set.
+    pub fn contains(&self, id: A::Id) -> bool {
+        let (word, mask) = Self::position(A::index(id));
+        self.arena_id == Some(A::arena_id(id))
+            && self.words.get(word).map_or(false, |w| w & mask != 0)
+    }
+
+    /// Remove every id from this set.
+    ///
+    /// The set still only accepts ids from the same arena afterwards.
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.len = 0;
+    }
+
+    /// Get the number of ids in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if this set contains no ids.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the ids in this set, in index order.
+    #[inline]
+    pub fn iter(&self) -> IdSetIter<'_, T, A> {
+        IntoIterator::into_iter(self)
+    }
+
+    #[inline]
+    fn position(index: usize) -> (usize, u64) {
+        (index / WORD_BITS, 1 << (index % WORD_BITS))
+    }
+}
+
+impl<T, A> Extend<A::Id> for IdSet<T, A>
+where
+    A: ArenaBehavior,
+{
+    fn extend<I: IntoIterator<Item = A::Id>>(&mut self, ids: I) {
+        for id in ids {
+            self.insert(id);
+        }
+    }
+}
+
+impl<T, A> iter::FromIterator<A::Id> for IdSet<T, A>
+where
+    A: ArenaBehavior,
+{
+    fn from_iter<I: IntoIterator<Item = A::Id>>(ids: I) -> IdSet<T, A> {
+        let mut set = IdSet::new();
+        set.extend(ids);
+        set
+    }
+}
+
+/// An iterator over the ids in an `IdSet`.
+///
+/// See [the `IdSet::iter()` method](./struct.IdSet.html#method.iter) for
+/// details.
+#[derive(Debug)]
+pub struct IdSetIter<'a, T, A> {
+    arena_id: u32,
+    words: iter::Enumerate<slice::Iter<'a, u64>>,
+    base: usize,
+    word: u64,
+    remaining: usize,
+    _phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<'a, T, A> Iterator for IdSetIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = A::Id;
+
+    fn next(&mut self) -> Option<A::Id> {
+        while self.word == 0 {
+            let (w, &word) = self.words.next()?;
+            self.base = w * WORD_BITS;
+            self.word = word;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        self.remaining -= 1;
+        Some(A::new_id(self.arena_id, self.base + bit))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, A> ExactSizeIterator for IdSetIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a IdSet<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = A::Id;
+    type IntoIter = IdSetIter<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> IdSetIter<'a, T, A> {
+        IdSetIter {
+            // An empty set without an arena id never yields any ids.
+            arena_id: self.arena_id.unwrap_or(0),
+            words: self.words.iter().enumerate(),
+            base: 0,
+            word: 0,
+            remaining: self.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_set_spans_words() {
+        let mut arena = Arena::<usize>::new();
+        let ids: Vec<_> = arena.alloc_extend(0..130).collect();
+
+        let mut set = IdSet::<usize>::new();
+        for &i in &[129, 0, 63, 64] {
+            assert!(set.insert(ids[i]));
+        }
+        assert!(!set.insert(ids[64]));
+        assert_eq!(set.len(), 4);
+        assert!(set.iter().map(|id| id.index()).eq([0, 63, 64, 129]));
+
+        assert!(set.remove(ids[129]));
+        assert!(!set.contains(ids[129]));
+        let expected: IdSet<usize> = [ids[0], ids[63], ids[64]].into_iter().collect();
+        assert_eq!(set, expected);
+    }
+}
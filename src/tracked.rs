@@ -0,0 +1,283 @@
+use super::*;
+
+/// An arena that records which of its objects were mutated or allocated.
+///
+/// Every id handed out by `get_mut`, `IndexMut` or `iter_mut` is recorded in a
+/// dirty set, which `take_dirty` returns and resets. Incremental passes can use
+/// it, together with `take_new`, to only revisit the objects that changed since
+/// the last pass.
+///
+/// ```
+/// use id_arena::TrackedArena;
+///
+/// let mut arena = TrackedArena::<u32>::new();
+/// let a = arena.alloc(1);
+/// let b = arena.alloc(2);
+/// assert_eq!(arena.take_new().collect::<Vec<_>>(), [a, b]);
+///
+/// arena[b] += 1;
+/// let c = arena.alloc(3);
+///
+/// assert_eq!(arena.take_dirty().iter().collect::<Vec<_>>(), [b]);
+/// assert_eq!(arena.take_new().collect::<Vec<_>>(), [c]);
+/// assert!(arena.take_dirty().is_empty());
+/// ```
+pub struct TrackedArena<T, A = DefaultArenaBehavior<T>> {
+    arena: Arena<T, A>,
+    dirty: IdSet<T, A>,
+    new_start: usize,
+}
+
+impl<T, A> fmt::Debug for TrackedArena<T, A>
+where
+    A: ArenaBehavior,
+    A::Id: fmt::Debug,
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TrackedArena")
+            .field("arena_id", &self.arena.arena_id)
+            .field("items", &self.arena.items)
+            .field("dirty", &self.dirty)
+            .field("new_start", &self.new_start)
+            .finish()
+    }
+}
+
+impl<T, A> Default for TrackedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn default() -> TrackedArena<T, A> {
+        Arena::new().into()
+    }
+}
+
+impl<T, A> From<Arena<T, A>> for TrackedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Start tracking changes to `arena`.
+    ///
+    /// The objects already in `arena` are not considered new.
+    fn from(arena: Arena<T, A>) -> TrackedArena<T, A> {
+        TrackedArena {
+            new_start: arena.len(),
+            arena,
+            dirty: IdSet::new(),
+        }
+    }
+}
+
+impl<T, A> TrackedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `TrackedArena`.
+    #[inline]
+    pub fn new() -> TrackedArena<T, A> {
+        Default::default()
+    }
+
+    /// Allocate `item` within this arena and return its id.
+    ///
+    /// The id is returned from the next call to `take_new`.
+    #[inline]
+    pub fn alloc(&mut self, item: T) -> A::Id {
+        self.arena.alloc(item)
+    }
+
+    /// Get the id that will be used for the next item allocated into this
+    /// arena.
+    #[inline]
+    pub fn next_id(&self) -> A::Id {
+        self.arena.next_id()
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    ///
+    /// This does not mark `id` as dirty.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        self.arena.get(id)
+    }
+
+    /// Get an exclusive reference to the object associated with the given `id`
+    /// if it exists, and mark `id` as dirty.
+    #[inline]
+    pub fn get_mut(&mut self, id: A::Id) -> Option<&mut T> {
+        let item = self.arena.get_mut(id)?;
+        self.dirty.insert(id);
+        Some(item)
+    }
+
+    /// Iterate over this arena's items and their ids.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        self.arena.iter()
+    }
+
+    /// Iterate over this arena's items and their ids, allowing mutation of
+    /// each item.
+    ///
+    /// Only the ids that the iterator actually yields are marked as dirty.
+    #[inline]
+    pub fn iter_mut(&mut self) -> TrackedIterMut<'_, T, A> {
+        TrackedIterMut {
+            iter: self.arena.iter_mut(),
+            dirty: &mut self.dirty,
+        }
+    }
+
+    /// Take the set of ids that were mutated since the last call to
+    /// `take_dirty`, leaving the dirty set empty.
+    #[inline]
+    pub fn take_dirty(&mut self) -> IdSet<T, A> {
+        core::mem::take(&mut self.dirty)
+    }
+
+    /// Take the range of ids that were allocated since the last call to
+    /// `take_new`.
+    #[inline]
+    pub fn take_new(&mut self) -> IdRange<T, A> {
+        let start = core::mem::replace(&mut self.new_start, self.arena.len());
+        IdRange::new(self.arena.arena_id, start, self.new_start)
+    }
+
+    /// Get a shared reference to the underlying arena.
+    #[inline]
+    pub fn as_arena(&self) -> &Arena<T, A> {
+        &self.arena
+    }
+
+    /// Stop tracking changes and return the underlying arena.
+    #[inline]
+    pub fn into_inner(self) -> Arena<T, A> {
+        self.arena
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+}
+
+impl<T, A> ops::Index<A::Id> for TrackedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        &self.arena[id]
+    }
+}
+
+impl<T, A> ops::IndexMut<A::Id> for TrackedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, id: A::Id) -> &mut T {
+        let item = &mut self.arena[id];
+        self.dirty.insert(id);
+        item
+    }
+}
+
+/// An iterator over `(Id, &mut T)` pairs in a `TrackedArena`, which marks each
+/// yielded id as dirty.
+///
+/// See [the `TrackedArena::iter_mut()`
+/// method](./struct.TrackedArena.html#method.iter_mut) for details.
+pub struct TrackedIterMut<'a, T: 'a, A: 'a> {
+    iter: IterMut<'a, T, A>,
+    dirty: &'a mut IdSet<T, A>,
+}
+
+impl<'a, T: 'a, A: 'a> fmt::Debug for TrackedIterMut<'a, T, A>
+where
+    T: fmt::Debug,
+    A: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TrackedIterMut")
+            .field("iter", &self.iter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for TrackedIterMut<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a mut T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, item) = self.iter.next()?;
+        self.dirty.insert(id);
+        Some((id, item))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let (id, item) = self.iter.nth(n)?;
+        self.dirty.insert(id);
+        Some((id, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for TrackedIterMut<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (id, item) = self.iter.next_back()?;
+        self.dirty.insert(id);
+        Some((id, item))
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for TrackedIterMut<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracked_iter_mut_marks_only_yielded_ids() {
+        let mut arena = TrackedArena::<u32>::from(Arena::from_iter(0..100));
+        assert!(arena.take_new().next().is_none());
+
+        for (_, x) in arena.iter_mut().skip(10).step_by(10).take(3) {
+            *x += 1;
+        }
+        let dirty: Vec<_> = arena.take_dirty().iter().map(|id| id.index()).collect();
+        assert_eq!(dirty, [10, 20, 30]);
+        assert!(arena.take_dirty().is_empty());
+    }
+}
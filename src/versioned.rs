@@ -0,0 +1,364 @@
+use super::*;
+
+/// An arena that stores a revision number alongside each of its objects.
+///
+/// The arena keeps a revision counter that is bumped by every allocation and
+/// every access through `get_mut`, `IndexMut` or `replace`, and the touched
+/// object is stamped with the new revision. Caches of data derived from an
+/// object can remember the `current_revision` they were computed at, and ask
+/// `changed_since` to find out whether they are stale.
+///
+/// Handing out an exclusive reference counts as a change, even if the object
+/// is not actually written to.
+///
+/// ```
+/// use id_arena::VersionedArena;
+///
+/// let mut arena = VersionedArena::<u32>::new();
+/// let a = arena.alloc(1);
+/// let b = arena.alloc(2);
+///
+/// let computed_at = arena.current_revision();
+/// arena[b] += 1;
+///
+/// assert!(!arena.changed_since(a, computed_at));
+/// assert!(arena.changed_since(b, computed_at));
+/// assert_eq!(arena.revision(b), arena.current_revision());
+/// ```
+pub struct VersionedArena<T, A = DefaultArenaBehavior<T>> {
+    arena_id: u32,
+    revision: u64,
+    items: Vec<(u64, T)>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<T, A> fmt::Debug for VersionedArena<T, A>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VersionedArena")
+            .field("arena_id", &self.arena_id)
+            .field("revision", &self.revision)
+            .field("items", &self.items)
+            .finish()
+    }
+}
+
+impl<T, A> Clone for VersionedArena<T, A>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        VersionedArena {
+            arena_id: self.arena_id,
+            revision: self.revision,
+            items: self.items.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> Default for VersionedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    fn default() -> VersionedArena<T, A> {
+        Arena::new().into()
+    }
+}
+
+impl<T, A> From<Arena<T, A>> for VersionedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Start versioning the objects in `arena`, which all get revision `0`.
+    fn from(arena: Arena<T, A>) -> VersionedArena<T, A> {
+        VersionedArena {
+            arena_id: arena.arena_id,
+            revision: 0,
+            items: arena.items.into_iter().map(|item| (0, item)).collect(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, A> VersionedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    /// Construct a new, empty `VersionedArena`.
+    #[inline]
+    pub fn new() -> VersionedArena<T, A> {
+        Default::default()
+    }
+
+    /// Allocate `item` within this arena and return its id.
+    ///
+    /// The new object is stamped with a new revision.
+    #[inline]
+    pub fn alloc(&mut self, item: T) -> A::Id {
+        let id = self.next_id();
+        let revision = self.bump();
+        self.items.push((revision, item));
+        id
+    }
+
+    /// Get the id that will be used for the next item allocated into this
+    /// arena.
+    #[inline]
+    pub fn next_id(&self) -> A::Id {
+        A::new_id(self.arena_id, self.items.len())
+    }
+
+    #[inline]
+    fn bump(&mut self) -> u64 {
+        self.revision += 1;
+        self.revision
+    }
+
+    #[inline]
+    fn slot(&self, id: A::Id) -> Result<usize, ArenaError> {
+        check_id(
+            self.arena_id,
+            self.items.len(),
+            A::arena_id(id),
+            A::index(id),
+        )
+    }
+
+    /// Get a shared reference to the object associated with the given `id` if
+    /// it exists.
+    #[inline]
+    pub fn get(&self, id: A::Id) -> Option<&T> {
+        self.try_get(id).ok()
+    }
+
+    /// Get a shared reference to the object associated with the given `id`,
+    /// or an error describing why there is none.
+    #[inline]
+    pub fn try_get(&self, id: A::Id) -> Result<&T, ArenaError> {
+        let index = self.slot(id)?;
+        Ok(&self.items[index].1)
+    }
+
+    /// Get an exclusive reference to the object associated with the given `id`
+    /// if it exists, and stamp it with a new revision.
+    #[inline]
+    pub fn get_mut(&mut self, id: A::Id) -> Option<&mut T> {
+        self.try_get_mut(id).ok()
+    }
+
+    /// Get an exclusive reference to the object associated with the given
+    /// `id` and stamp it with a new revision, or return an error describing
+    /// why there is no such object.
+    #[inline]
+    pub fn try_get_mut(&mut self, id: A::Id) -> Result<&mut T, ArenaError> {
+        let index = self.slot(id)?;
+        let revision = self.bump();
+        let (slot_revision, item) = &mut self.items[index];
+        *slot_revision = revision;
+        Ok(item)
+    }
+
+    /// Replace the object associated with the given `id` with `item`, stamp
+    /// it with a new revision, and return the old object.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no object associated with `id` in this arena.
+    #[inline]
+    #[track_caller]
+    pub fn replace(&mut self, id: A::Id, item: T) -> T {
+        core::mem::replace(&mut self[id], item)
+    }
+
+    /// Get the revision that the object associated with the given `id` was
+    /// last allocated or changed at.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no object associated with `id` in this arena.
+    #[inline]
+    #[track_caller]
+    pub fn revision(&self, id: A::Id) -> u64 {
+        match self.slot(id) {
+            Ok(index) => self.items[index].0,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+
+    /// Returns `true` if the object associated with the given `id` was
+    /// allocated or changed after `revision`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is no object associated with `id` in this arena.
+    #[inline]
+    #[track_caller]
+    pub fn changed_since(&self, id: A::Id, revision: u64) -> bool {
+        self.revision(id) > revision
+    }
+
+    /// Get the revision of the most recent allocation or change in this
+    /// arena.
+    ///
+    /// This is `0` for a new, empty arena.
+    #[inline]
+    pub fn current_revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Iterate over this arena's items and their ids.
+    ///
+    /// This does not change any revisions.
+    #[inline]
+    pub fn iter(&self) -> VersionedIter<'_, T, A> {
+        VersionedIter {
+            arena_id: self.arena_id,
+            iter: self.items.iter().enumerate(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Stop versioning the objects in this arena and return them in an
+    /// `Arena` with the same arena id.
+    pub fn into_inner(self) -> Arena<T, A> {
+        Arena {
+            arena_id: self.arena_id,
+            items: self.items.into_iter().map(|(_, item)| item).collect(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get the number of objects allocated in this arena.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if no objects have been allocated in this arena.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<T, A> ops::Index<A::Id> for VersionedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, id: A::Id) -> &T {
+        match self.try_get(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+impl<T, A> ops::IndexMut<A::Id> for VersionedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, id: A::Id) -> &mut T {
+        match self.try_get_mut(id) {
+            Ok(item) => item,
+            Err(err) => index_panic::<T>(err),
+        }
+    }
+}
+
+/// An iterator over `(Id, &T)` pairs in a `VersionedArena`.
+///
+/// See [the `VersionedArena::iter()`
+/// method](./struct.VersionedArena.html#method.iter) for details.
+#[derive(Debug)]
+pub struct VersionedIter<'a, T: 'a, A: 'a> {
+    arena_id: u32,
+    iter: iter::Enumerate<slice::Iter<'a, (u64, T)>>,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+impl<'a, T: 'a, A: 'a> Iterator for VersionedIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, (_, item)) = self.iter.next()?;
+        Some((A::new_id(self.arena_id, index), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: 'a, A: 'a> DoubleEndedIterator for VersionedIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (index, (_, item)) = self.iter.next_back()?;
+        Some((A::new_id(self.arena_id, index), item))
+    }
+}
+
+impl<'a, T: 'a, A: 'a> ExactSizeIterator for VersionedIter<'a, T, A>
+where
+    A: ArenaBehavior,
+{
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<'a, T, A> IntoIterator for &'a VersionedArena<T, A>
+where
+    A: ArenaBehavior,
+{
+    type Item = (A::Id, &'a T);
+    type IntoIter = VersionedIter<'a, T, A>;
+
+    #[inline]
+    fn into_iter(self) -> VersionedIter<'a, T, A> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versioned_arena_stamps_only_changed_slots() {
+        let mut arena = VersionedArena::<u32>::new();
+        let a = arena.alloc(0);
+        let b = arena.alloc(0);
+        let rev = arena.current_revision();
+        assert_eq!((arena.revision(a), arena.revision(b), rev), (1, 2, 2));
+
+        arena.get(a);
+        let _ = arena[a];
+        assert!(!arena.changed_since(a, rev));
+        assert_eq!(arena.replace(a, 1), 0);
+        *arena.get_mut(b).unwrap() += 1;
+        assert_eq!((arena.revision(a), arena.revision(b)), (3, 4));
+        assert!(arena.changed_since(a, rev) && !arena.changed_since(a, 3));
+
+        let other = Arena::<u32>::new();
+        assert!(arena.get_mut(other.next_id()).is_none());
+        assert_eq!(arena.current_revision(), 4);
+        assert_eq!(arena.iter().rev().collect::<Vec<_>>(), [(b, &1), (a, &1)]);
+        assert_eq!(arena.iter().len(), 2);
+    }
+}
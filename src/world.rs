@@ -0,0 +1,351 @@
+use super::*;
+
+use core::any::{Any, TypeId};
+
+/// The marker type of the entities in a `World`, whose ids are `Id<Entity>`.
+///
+/// Entities have no data of their own: everything about an entity is stored in
+/// its components.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Entity {
+    _private: (),
+}
+
+/// A container of entities, each of which may have at most one component of
+/// every type.
+///
+/// An entity's id is allocated once with `spawn`, and is then the key for all
+/// of its components. The components of each type are stored in their own
+/// [`ArenaMap`](./struct.ArenaMap.html), so components of types that most
+/// entities have are stored densely, and `join2` and `join3` iterate over the
+/// entities that have components of several types.
+///
+/// Components can be of any `'static` type, including types that are not
+/// `Send` or `Sync`, such as `Rc`. To share the world between threads, use a
+/// [`SyncWorld`](./type.SyncWorld.html) instead, which only holds `Send + Sync`
+/// components.
+///
+/// ```
+/// use id_arena::World;
+///
+/// struct Position(i32, i32);
+/// struct Velocity(i32, i32);
+/// struct Name(&'static str);
+///
+/// let mut world = World::new();
+///
+/// let player = world.spawn();
+/// world.insert(player, Name("player"));
+/// world.insert(player, Position(0, 0));
+/// world.insert(player, Velocity(1, 0));
+///
+/// let rock = world.spawn();
+/// world.insert(rock, Position(5, 5));
+///
+/// let moving: Vec<_> = world
+///     .join2::<Position, Velocity>()
+///     .map(|(entity, _, _)| entity)
+///     .collect();
+/// assert_eq!(moving, [player]);
+///
+/// world.get_mut::<Position>(player).unwrap().0 += 1;
+/// assert_eq!(world.get::<Position>(player).unwrap().0, 1);
+/// assert!(world.get::<Name>(rock).is_none());
+/// ```
+pub struct World<S: ?Sized = dyn Any> {
+    entities: Arena<Entity>,
+    components: BTreeMap<TypeId, Box<S>>,
+}
+
+/// A `World` that only holds `Send + Sync` components, so that it can be
+/// shared between threads.
+///
+/// ```
+/// use id_arena::SyncWorld;
+///
+/// let mut world = SyncWorld::default();
+/// let entity = world.spawn();
+/// world.insert(entity, 42u32);
+///
+/// let world = std::thread::spawn(move || world).join().unwrap();
+/// assert_eq!(world.get::<u32>(entity), Some(&42));
+/// ```
+pub type SyncWorld = World<dyn Any + Send + Sync>;
+
+impl<S> Default for World<S>
+where
+    S: ?Sized,
+{
+    #[inline]
+    fn default() -> Self {
+        World {
+            entities: Arena::new(),
+            components: BTreeMap::new(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for World<S>
+where
+    S: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("World")
+            .field("entities", &self.entities.len())
+            .field("component_types", &self.components.len())
+            .finish()
+    }
+}
+
+impl World {
+    /// Construct a new, empty `World`.
+    #[inline]
+    pub fn new() -> World {
+        Default::default()
+    }
+}
+
+impl<S> World<S>
+where
+    S: ?Sized + AnyStorage,
+{
+    /// Allocate a new entity without any components, and return its id.
+    #[inline]
+    pub fn spawn(&mut self) -> Id<Entity> {
+        self.entities.alloc(Entity::default())
+    }
+
+    /// Returns `true` if `entity` was spawned in this world.
+    #[inline]
+    pub fn contains(&self, entity: Id<Entity>) -> bool {
+        self.entities.get(entity).is_some()
+    }
+
+    /// Give `entity` the component `component`, and return the component of
+    /// the same type that it had before, if any.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `entity` was not spawned in this world.
+    #[track_caller]
+    pub fn insert<C>(&mut self, entity: Id<Entity>, component: C) -> Option<C>
+    where
+        C: StorableIn<S>,
+    {
+        if let Err(err) = self.entities.try_get(entity) {
+            panic!("cannot insert component: {}", err);
+        }
+        self.components
+            .entry(TypeId::of::<C>())
+            .or_insert_with(|| C::store_components(ArenaMap::new()))
+            .as_any_mut()
+            .downcast_mut::<ArenaMap<Entity, C>>()
+            .unwrap()
+            .insert(entity, component)
+    }
+
+    /// Remove the component of type `C` from `entity`, and return it if it
+    /// had one.
+    #[inline]
+    pub fn remove<C>(&mut self, entity: Id<Entity>) -> Option<C>
+    where
+        C: StorableIn<S>,
+    {
+        self.components_mut::<C>()?.remove(entity)
+    }
+
+    /// Get a shared reference to `entity`'s component of type `C`, if any.
+    #[inline]
+    pub fn get<C>(&self, entity: Id<Entity>) -> Option<&C>
+    where
+        C: StorableIn<S>,
+    {
+        self.components::<C>()?.get(entity)
+    }
+
+    /// Get an exclusive reference to `entity`'s component of type `C`, if any.
+    #[inline]
+    pub fn get_mut<C>(&mut self, entity: Id<Entity>) -> Option<&mut C>
+    where
+        C: StorableIn<S>,
+    {
+        self.components_mut::<C>()?.get_mut(entity)
+    }
+
+    /// Get the map of every entity's component of type `C`, if any entity
+    /// has ever been given one.
+    #[inline]
+    pub fn components<C>(&self) -> Option<&ArenaMap<Entity, C>>
+    where
+        C: StorableIn<S>,
+    {
+        self.components
+            .get(&TypeId::of::<C>())?
+            .as_any()
+            .downcast_ref()
+    }
+
+    /// Get the map of every entity's component of type `C` for mutation, if
+    /// any entity has ever been given one.
+    #[inline]
+    pub fn components_mut<C>(&mut self) -> Option<&mut ArenaMap<Entity, C>>
+    where
+        C: StorableIn<S>,
+    {
+        self.components
+            .get_mut(&TypeId::of::<C>())?
+            .as_any_mut()
+            .downcast_mut()
+    }
+
+    /// Iterate over the entities that have components of both types `C1` and
+    /// `C2`, together with those components, in order of the entities' ids.
+    pub fn join2<C1, C2>(&self) -> impl Iterator<Item = (Id<Entity>, &C1, &C2)>
+    where
+        C1: StorableIn<S>,
+        C2: StorableIn<S>,
+    {
+        let second = self.components::<C2>();
+        self.components::<C1>()
+            .into_iter()
+            .flat_map(|first| first.iter())
+            .filter_map(move |(entity, a)| Some((entity, a, second?.get(entity)?)))
+    }
+
+    /// Iterate over the entities that have components of all three types
+    /// `C1`, `C2` and `C3`, together with those components, in order of the
+    /// entities' ids.
+    pub fn join3<C1, C2, C3>(&self) -> impl Iterator<Item = (Id<Entity>, &C1, &C2, &C3)>
+    where
+        C1: StorableIn<S>,
+        C2: StorableIn<S>,
+        C3: StorableIn<S>,
+    {
+        let third = self.components::<C3>();
+        self.join2::<C1, C2>()
+            .filter_map(move |(entity, a, b)| Some((entity, a, b, third?.get(entity)?)))
+    }
+
+    /// Iterate over the ids of every entity in this world.
+    #[inline]
+    pub fn entities(&self) -> Entities<'_> {
+        Entities {
+            iter: self.entities.iter(),
+        }
+    }
+
+    /// Get the number of entities in this world.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if no entities have been spawned in this world.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// An iterator over the ids of every entity in a `World`.
+///
+/// See [the `World::entities()` method](./struct.World.html#method.entities)
+/// for details.
+#[derive(Debug)]
+pub struct Entities<'a> {
+    iter: Iter<'a, Entity, DefaultArenaBehavior<Entity>>,
+}
+
+impl<'a> Iterator for Entities<'a> {
+    type Item = Id<Entity>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Id<Entity>> {
+        self.iter.next().map(|(entity, _)| entity)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Entities<'a> {
+    fn next_back(&mut self) -> Option<Id<Entity>> {
+        self.iter.next_back().map(|(entity, _)| entity)
+    }
+}
+
+impl<'a> ExactSizeIterator for Entities<'a> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_worlds_are_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SyncWorld>();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn worlds_hold_components_that_are_not_send() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, std::rc::Rc::new(core::cell::Cell::new(1u32)));
+        world
+            .get::<std::rc::Rc<core::cell::Cell<u32>>>(entity)
+            .unwrap()
+            .set(2);
+        assert_eq!(
+            world
+                .remove::<std::rc::Rc<core::cell::Cell<u32>>>(entity)
+                .unwrap()
+                .get(),
+            2
+        );
+    }
+
+    #[test]
+    fn world_joins_entities_with_every_component() {
+        let mut world = World::new();
+        let entities: Vec<_> = (0..7).map(|_| world.spawn()).collect();
+        for (i, &entity) in entities.iter().enumerate() {
+            if i % 2 == 0 {
+                world.insert(entity, i as u32);
+            }
+            if i % 3 == 0 {
+                world.insert(entity, i as u64);
+            }
+            world.insert(entity, 'x');
+        }
+        assert_eq!(world.remove::<char>(entities[0]), Some('x'));
+        assert_eq!(world.remove::<char>(entities[0]), None);
+
+        let joined: Vec<_> = world
+            .join3::<u32, u64, char>()
+            .map(|(entity, &a, &b, _)| (entity, a, b))
+            .collect();
+        assert_eq!(joined, [(entities[6], 6, 6)]);
+        assert_eq!(world.components::<u32>().unwrap().len(), 4);
+        assert!(world.join2::<u32, bool>().next().is_none());
+
+        let evens = world.components::<u32>().unwrap();
+        assert_eq!(evens.iter().len(), 4);
+        let last_evens: Vec<_> = evens.iter().rev().take(2).map(|(_, &i)| i).collect();
+        assert_eq!(last_evens, [6, 4]);
+        assert_eq!(world.entities().len(), 7);
+        assert_eq!(world.entities().next_back(), Some(entities[6]));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot insert component")]
+    fn world_rejects_entities_from_other_worlds() {
+        let entity = World::new().spawn();
+        World::new().insert(entity, 0u32);
+    }
+}
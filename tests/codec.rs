@@ -0,0 +1,191 @@
+#![cfg(feature = "alloc")]
+
+use id_arena::{Arena, Codec, DecodeError, Decoder, Encoder, Id};
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Num(i64),
+    Neg(Id<Expr>),
+    Add(Id<Expr>, Id<Expr>),
+}
+
+impl Codec for Expr {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            Expr::Num(n) => {
+                encoder.write_u8(0);
+                n.encode(encoder);
+            }
+            Expr::Neg(x) => {
+                encoder.write_u8(1);
+                x.encode(encoder);
+            }
+            Expr::Add(a, b) => {
+                encoder.write_u8(2);
+                a.encode(encoder);
+                b.encode(encoder);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Expr, DecodeError> {
+        match decoder.read_u8()? {
+            0 => Ok(Expr::Num(i64::decode(decoder)?)),
+            1 => Ok(Expr::Neg(Id::decode(decoder)?)),
+            2 => Ok(Expr::Add(Id::decode(decoder)?, Id::decode(decoder)?)),
+            _ => Err(DecodeError::Invalid("unknown `Expr` tag")),
+        }
+    }
+}
+
+/// Build a chain of `len` expressions, each of which refers to the one before.
+fn chain(len: usize) -> Arena<Expr> {
+    let mut arena = Arena::new();
+    let mut prev = arena.alloc(Expr::Num(-1));
+    for i in 1..len {
+        prev = arena.alloc(if i % 2 == 0 {
+            Expr::Neg(prev)
+        } else {
+            Expr::Add(prev, prev)
+        });
+    }
+    arena
+}
+
+fn round_trip(arena: &Arena<Expr>, relative_ids: bool) -> (Arena<Expr>, usize) {
+    let mut encoder = Encoder::new();
+    encoder.set_relative_ids(relative_ids);
+    arena.encode(&mut encoder);
+    let mut decoder = Decoder::new(encoder.as_bytes());
+    let decoded = Arena::decode(&mut decoder).unwrap();
+    decoder.finish().unwrap();
+    (decoded, encoder.as_bytes().len())
+}
+
+/// Replace every id in `expr` with its index, so that expressions from
+/// different arenas can be compared.
+fn by_index(expr: &Expr) -> (u8, i64, usize, usize) {
+    match *expr {
+        Expr::Num(n) => (0, n, 0, 0),
+        Expr::Neg(x) => (1, 0, x.index(), 0),
+        Expr::Add(a, b) => (2, 0, a.index(), b.index()),
+    }
+}
+
+#[test]
+fn arena_round_trips_with_absolute_and_relative_ids() {
+    let arena = chain(1000);
+    for relative_ids in [false, true] {
+        let (decoded, _) = round_trip(&arena, relative_ids);
+        assert_eq!(decoded.len(), arena.len());
+        for ((_, expected), (id, actual)) in arena.iter().zip(decoded.iter()) {
+            assert_eq!(by_index(expected), by_index(actual));
+            // Decoded ids belong to the decoded arena.
+            if let Expr::Neg(x) | Expr::Add(x, _) = *actual {
+                assert!(decoded.get(x).is_some());
+                assert!(arena.get(x).is_none());
+                assert!(x < id);
+            }
+        }
+    }
+}
+
+#[test]
+fn relative_ids_are_smaller_for_nearby_references() {
+    let arena = chain(1000);
+    let (_, absolute) = round_trip(&arena, false);
+    let (_, relative) = round_trip(&arena, true);
+    assert!(relative < absolute, "{} >= {}", relative, absolute);
+}
+
+#[test]
+fn out_of_bounds_ids_are_rejected() {
+    // Not relative, one item: `Neg` of the item at index 1, tagged as an id
+    // of the same arena.
+    let bytes = [0, 1, 1, 2];
+    assert_eq!(
+        Arena::<Expr>::decode(&mut Decoder::new(&bytes)).unwrap_err(),
+        DecodeError::Invalid("id is out of bounds")
+    );
+
+    // Relative, one item: `Neg` of the item at offset -1.
+    let bytes = [1, 1, 1, 2];
+    assert!(Arena::<Expr>::decode(&mut Decoder::new(&bytes)).is_err());
+}
+
+#[test]
+fn ids_outside_of_an_arena_round_trip() {
+    let mut arena = Arena::<Expr>::new();
+    arena.alloc(Expr::Num(0));
+    let id = arena.alloc(Expr::Num(1));
+    let mut encoder = Encoder::new();
+    id.encode(&mut encoder);
+    assert_eq!(Id::decode(&mut Decoder::new(encoder.as_bytes())), Ok(id));
+}
+
+#[test]
+fn ids_cannot_be_decoded_outside_of_an_arena() {
+    let mut encoder = Encoder::new();
+    0usize.encode(&mut encoder);
+    assert!(Id::<Expr>::decode(&mut Decoder::new(encoder.as_bytes())).is_err());
+}
+
+#[test]
+#[should_panic(expected = "cannot encode id: index 1 is out of bounds for length 1")]
+fn ids_out_of_bounds_cannot_be_encoded() {
+    let mut longer = Arena::<Expr>::with_arena_id(3);
+    longer.alloc(Expr::Num(0));
+    let out_of_bounds = longer.alloc(Expr::Num(1));
+    let mut arena = Arena::<Expr>::with_arena_id(3);
+    arena.alloc(Expr::Neg(out_of_bounds));
+    arena.encode(&mut Encoder::new());
+}
+
+#[test]
+fn ids_from_other_arenas_round_trip() {
+    let mut other = Arena::<Expr>::with_arena_id(5);
+    other.alloc(Expr::Num(0));
+    let foreign = other.alloc(Expr::Num(1));
+    let mut arena = Arena::<Expr>::new();
+    let own = arena.alloc(Expr::Num(2));
+    arena.alloc(Expr::Add(own, foreign));
+
+    for relative in [false, true] {
+        let mut encoder = Encoder::new();
+        encoder.set_relative_ids(relative);
+        arena.encode(&mut encoder);
+        let decoded = Arena::<Expr>::decode(&mut Decoder::new(encoder.as_bytes())).unwrap();
+        let (_, add) = decoded.iter().next_back().unwrap();
+        match *add {
+            Expr::Add(own, foreign_again) => {
+                assert_eq!(decoded[own], Expr::Num(2));
+                assert_eq!(foreign_again, foreign);
+                assert_eq!(other[foreign_again], Expr::Num(1));
+            }
+            _ => panic!("expected `Expr::Add`"),
+        }
+    }
+}
+
+#[test]
+fn primitives_round_trip() {
+    let value = (
+        (u64::MAX, i64::MIN),
+        (
+            ('λ', String::from("id-arena")),
+            (std::vec![Some(-1i8), None, Some(127)], (true, 0.5f64)),
+        ),
+    );
+    let mut encoder = Encoder::new();
+    value.encode(&mut encoder);
+    let mut decoder = Decoder::new(encoder.as_bytes());
+    assert_eq!(Codec::decode(&mut decoder), Ok(value));
+    assert_eq!(decoder.finish(), Ok(()));
+
+    let overflow = [0xff; 10];
+    assert!(u64::decode(&mut Decoder::new(&overflow)).is_err());
+    assert_eq!(
+        u8::decode(&mut Decoder::new(&[])),
+        Err(DecodeError::UnexpectedEnd)
+    );
+}